@@ -0,0 +1,43 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+
+use trilogy_save_editor::{save_data::mass_effect_1_le::legacy::ClassSizes, save_game::SaveKind};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Platform {
+    Pc,
+    Xbox360,
+}
+
+#[derive(Args)]
+pub struct Convert {
+    /// Save path (Mass Effect 2 or 3)
+    save: PathBuf,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+    /// Target platform
+    #[arg(short, long, value_enum)]
+    platform: Platform,
+}
+
+impl Convert {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Convert { save, output, platform } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        match save_game {
+            SaveKind::MassEffect2(_) | SaveKind::MassEffect3(_) => (),
+            _ => bail!("Only Mass Effect 2 and 3 saves can be converted between PC and Xbox 360"),
+        }
+
+        let is_xbox360 = matches!(platform, Platform::Xbox360);
+        let new_save = save_game.serialize_for_platform(is_xbox360)?;
+
+        super::write_new_save(&new_save, &output)
+    }
+}
@@ -0,0 +1,65 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use trilogy_save_editor::{save_data::mass_effect_1_le::legacy::ClassSizes, save_diff, save_game::SaveKind};
+
+#[derive(Args)]
+pub struct Diff {
+    /// Old save path
+    old_save: PathBuf,
+    /// New save path, from the same game
+    new_save: PathBuf,
+    /// Print the differences as JSON
+    #[arg(long)]
+    json: bool,
+    /// Directory with custom plot databases (me1_plot_db.ron, me2_plot_db.ron, me3_plot_db.ron)
+    #[arg(short, long, value_name = "DIR")]
+    database_dir: Option<PathBuf>,
+}
+
+impl Diff {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Diff {
+            old_save,
+            new_save,
+            json,
+            database_dir,
+        } = self;
+
+        let old_file = fs::read(old_save).context("Failed to open the old save file")?;
+        let old = SaveKind::deserialize_with(old_file, class_sizes).context("Failed to parse the old save file")?;
+        let new_file = fs::read(new_save).context("Failed to open the new save file")?;
+        let new = SaveKind::deserialize_with(new_file, class_sizes).context("Failed to parse the new save file")?;
+
+        let diff = save_diff::diff(&old, &new, database_dir.as_deref())?;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+            return Ok(());
+        }
+
+        if diff.is_empty() {
+            println!("No differences");
+        }
+        for plot in &diff.plot {
+            println!("{plot}");
+        }
+        for field in &diff.fields {
+            match (&field.old, &field.new) {
+                (None, Some(new)) => println!("+ {} = {}", field.path, super::display(new)?),
+                (Some(old), None) => println!("- {} = {}", field.path, super::display(old)?),
+                (Some(old), Some(new)) => println!(
+                    "~ {} = {} -> {}",
+                    field.path,
+                    super::display(old)?,
+                    super::display(new)?
+                ),
+                (None, None) => (),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,335 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter, history::DefaultHistory,
+    validate::Validator, Editor, Helper,
+};
+use serde_yaml::Value;
+
+use trilogy_save_editor::{
+    reflect::{self, Change, FieldPath, PathSegment},
+    save_data::mass_effect_1_le::legacy::ClassSizes,
+    save_game::SaveKind,
+};
+
+const UNWRITTEN: &str = "The changes are not written, `write` them or `quit!` to discard them";
+
+const HELP: &str = "\
+ls [PATH]            List the fields of PATH, or of the whole save
+get PATH             Print a field
+set PATH VALUE       Change a field, VALUE has the same type as the current one
+undo                 Undo the last change
+redo                 Redo the last undone change
+diff                 Print every change since the save was loaded
+write [NEW_SAVE]     Write the save, to the output path by default
+quit                 Leave, `quit!` discards the unwritten changes
+help                 Print this help
+
+Field paths are completed with <Tab>, e.g. `player.credits` or `galaxy_map.planets[5].visited`";
+
+#[derive(Args)]
+pub struct Edit {
+    /// Save path
+    save: PathBuf,
+    /// New save path, the save itself by default (a backup is made)
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: Option<PathBuf>,
+}
+
+impl Edit {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Edit { save, output } = self;
+
+        let save_file = fs::read(&save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+        let tree = reflect::to_value(&save_game)?;
+
+        let mut session = Session {
+            is_xbox360: save_game.is_xbox360(),
+            original: tree.clone(),
+            output: output.unwrap_or(save),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            is_written: true,
+        };
+
+        // The working tree lives in the helper for completion, it is only deserialized on `write`
+        let mut editor: Editor<PathHelper, DefaultHistory> = Editor::new()?;
+        editor.set_helper(Some(PathHelper { tree }));
+
+        println!("Type `help` for the list of commands");
+        loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) if !session.is_written => {
+                    println!("{UNWRITTEN}");
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err.into()),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line)?;
+
+            let (command, args) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let tree = &mut editor.helper_mut().context("No helper")?.tree;
+            let result = match command {
+                "ls" => session.list(tree, args.trim()),
+                "get" => session.get(tree, args.trim()),
+                "set" => session.set(tree, args.trim()),
+                "undo" => session.undo(tree),
+                "redo" => session.redo(tree),
+                "diff" => session.diff(tree),
+                "write" => session.write(tree, args.trim()),
+                "quit" | "exit" if !session.is_written => {
+                    println!("{UNWRITTEN}");
+                    Ok(())
+                }
+                "quit" | "exit" | "quit!" | "exit!" => break,
+                "help" => {
+                    println!("{HELP}");
+                    Ok(())
+                }
+                _ => Err(anyhow!(
+                    "Unknown command `{command}`, type `help` for the list of commands"
+                )),
+            };
+
+            if let Err(err) = result {
+                println!("Error: {err:#}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct FieldEdit {
+    path: FieldPath,
+    old_value: Value,
+    new_value: Value,
+}
+
+struct Session {
+    // Not part of the tree
+    is_xbox360: bool,
+    // The tree of the save as loaded, for `diff`
+    original: Value,
+    output: PathBuf,
+    undo: Vec<FieldEdit>,
+    redo: Vec<FieldEdit>,
+    is_written: bool,
+}
+
+impl Session {
+    fn list(&self, tree: &Value, path: &str) -> Result<()> {
+        let path: FieldPath = path.parse()?;
+        let value = reflect::get(tree, &path)?;
+
+        let children = reflect::children(value);
+        if children.is_empty() {
+            println!("{}", super::display(value)?);
+        }
+        for (segment, value) in children {
+            let summary = match reflect::children(value).len() {
+                0 => super::display(value)?,
+                len => format!("({len} fields)"),
+            };
+            println!("{} = {summary}", FieldPath(vec![segment]));
+        }
+        Ok(())
+    }
+
+    fn get(&self, tree: &Value, path: &str) -> Result<()> {
+        let path: FieldPath = path.parse()?;
+        println!("{path} = {}", super::display(reflect::get(tree, &path)?)?);
+        Ok(())
+    }
+
+    fn set(&mut self, tree: &mut Value, args: &str) -> Result<()> {
+        let (path, value) = args.split_once(char::is_whitespace).context("Usage: set PATH VALUE")?;
+        let path: FieldPath = path.parse()?;
+        let value = value.trim();
+
+        let old_value = reflect::set(tree, &path, value)?;
+        let new_value = reflect::get(tree, &path)?.clone();
+        println!(
+            "{path} = {} -> {}",
+            super::display(&old_value)?,
+            super::display(&new_value)?
+        );
+
+        self.undo.push(FieldEdit {
+            path,
+            old_value,
+            new_value,
+        });
+        self.redo.clear();
+        self.is_written = false;
+        Ok(())
+    }
+
+    fn undo(&mut self, tree: &mut Value) -> Result<()> {
+        let edit = self.undo.pop().context("Nothing to undo")?;
+        reflect::replace(tree, &edit.path, edit.old_value.clone())?;
+        println!("{} = {}", edit.path, super::display(&edit.old_value)?);
+
+        self.redo.push(edit);
+        self.is_written = false;
+        Ok(())
+    }
+
+    fn redo(&mut self, tree: &mut Value) -> Result<()> {
+        let edit = self.redo.pop().context("Nothing to redo")?;
+        reflect::replace(tree, &edit.path, edit.new_value.clone())?;
+        println!("{} = {}", edit.path, super::display(&edit.new_value)?);
+
+        self.undo.push(edit);
+        self.is_written = false;
+        Ok(())
+    }
+
+    fn diff(&self, tree: &Value) -> Result<()> {
+        let changes = reflect::diff(&self.original, tree);
+        if changes.is_empty() {
+            println!("No changes");
+        }
+        for change in changes {
+            match change {
+                Change::Added(path, value) => println!("+ {path} = {}", super::display(&value)?),
+                Change::Removed(path, value) => println!("- {path} = {}", super::display(&value)?),
+                Change::Modified(path, old_value, new_value) => println!(
+                    "~ {path} = {} -> {}",
+                    super::display(&old_value)?,
+                    super::display(&new_value)?
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, tree: &Value, output: &str) -> Result<()> {
+        let output = match output {
+            "" => self.output.clone(),
+            output => PathBuf::from(output),
+        };
+        if output.is_dir() {
+            bail!("`{}` is a directory", output.display());
+        }
+
+        // A value of the right type can still be out of range, `undo` it
+        let save_game = reflect::from_value(tree.clone(), self.is_xbox360).context("Invalid save")?;
+        super::write_save(&save_game, &output)?;
+        println!("Written to `{}`", output.display());
+
+        self.is_written = true;
+        Ok(())
+    }
+}
+
+struct PathHelper {
+    tree: Value,
+}
+
+impl Completer for PathHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &rustyline::Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let line = &line[..pos];
+        // Only the first argument is a path
+        let start = match line.split_once(char::is_whitespace) {
+            Some((_, arg)) if !arg.trim_start().contains(char::is_whitespace) => pos - arg.trim_start().len(),
+            _ => return Ok((pos, Vec::new())),
+        };
+        let word = &line[start..];
+
+        // The parent path is complete, the last segment is being typed
+        let (parent, partial) = match word.rfind(['.', '[']) {
+            Some(idx) => word.split_at(idx),
+            None => ("", word),
+        };
+        let parent_value = match parent
+            .parse()
+            .ok()
+            .and_then(|path| reflect::get(&self.tree, &path).ok())
+        {
+            Some(value) => value,
+            None => return Ok((pos, Vec::new())),
+        };
+
+        let candidates = reflect::children(parent_value)
+            .into_iter()
+            .map(|(segment, _)| match segment {
+                PathSegment::Field(field) if !parent.is_empty() => format!(".{field}"),
+                segment => FieldPath(vec![segment]).to_string(),
+            })
+            .filter(|child| child.starts_with(partial))
+            .map(|child| format!("{parent}{child}"))
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for PathHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PathHelper {}
+
+impl Validator for PathHelper {}
+
+impl Helper for PathHelper {}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use trilogy_save_editor::save_game::SaveGame;
+
+    use super::*;
+
+    #[test]
+    fn session() -> Result<()> {
+        let save_game = SaveKind::deserialize(fs::read("test/ME2Save360.xbsav")?)?;
+        let mut tree = reflect::to_value(&save_game)?;
+        let output = env::temp_dir().join(format!("tse_edit_{}.xbsav", process::id()));
+        let mut session = Session {
+            is_xbox360: save_game.is_xbox360(),
+            original: tree.clone(),
+            output: output.clone(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            is_written: true,
+        };
+
+        let credits: FieldPath = "player.credits".parse()?;
+        session.set(&mut tree, "player.credits 1234")?;
+        session.set(&mut tree, "player.credits 5678")?;
+        session.undo(&mut tree)?;
+        assert_eq!(reflect::get(&tree, &credits)?, &Value::from(1234));
+        session.redo(&mut tree)?;
+        assert!(session.redo(&mut tree).is_err());
+        assert!(!session.is_written);
+
+        session.write(&tree, "")?;
+        assert!(session.is_written);
+        let save_game = SaveKind::deserialize(fs::read(&output)?)?;
+        fs::remove_file(&output)?;
+        assert!(save_game.is_xbox360());
+        assert_eq!(save_game.credits(), Some(5678));
+
+        // Only checked once deserialized
+        session.set(&mut tree, "squad[2].talent_points 9999999999")?;
+        assert!(session.write(&tree, "").is_err());
+        assert!(!output.exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use trilogy_save_editor::{
+    reflect::{self, FieldPath},
+    save_data::mass_effect_1_le::legacy::ClassSizes,
+    save_game::SaveKind,
+};
+
+#[derive(Args)]
+pub struct GetField {
+    /// Save path
+    save: PathBuf,
+    /// Field path, e.g. `player.credits` or `galaxy_map.planets[5].visited`
+    path: FieldPath,
+}
+
+impl GetField {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let GetField { save, path } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let value = reflect::get_field(&save_game, &path)?;
+        println!("{path} = {}", super::display(&value)?);
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct SetField {
+    /// Save path
+    save: PathBuf,
+    /// Field path, e.g. `player.credits` or `squad[2].talent_points`
+    path: FieldPath,
+    /// New value, of the same type as the current one
+    value: String,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+}
+
+impl SetField {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let SetField {
+            save,
+            path,
+            value,
+            output,
+        } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let mut save_game =
+            SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let old_value = reflect::edit(&mut save_game, |root| reflect::set(root, &path, &value))?;
+        let new_value = reflect::get_field(&save_game, &path)?;
+        println!(
+            "{path} = {} -> {}",
+            super::display(&old_value)?,
+            super::display(&new_value)?
+        );
+
+        super::write_save(&save_game, &output)
+    }
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Format {
+    #[default]
+    Ron,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?;
+        if ext.eq_ignore_ascii_case("ron") {
+            Some(Format::Ron)
+        } else if ext.eq_ignore_ascii_case("json") {
+            Some(Format::Json)
+        } else if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") {
+            Some(Format::Yaml)
+        } else {
+            None
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Ron => "ron",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    pub fn serialize<T>(self, value: &T) -> Result<String>
+    where
+        T: Serialize,
+    {
+        let string = match self {
+            Format::Ron => {
+                let pretty_config = PrettyConfig::new().enumerate_arrays(true).new_line(String::from('\n'));
+                ron::ser::to_string_pretty(value, pretty_config)?
+            }
+            Format::Json => serde_json::to_string_pretty(value)?,
+            Format::Yaml => serde_yaml::to_string(value)?,
+        };
+        Ok(string)
+    }
+
+    pub fn deserialize<T>(self, input: &[u8]) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let value = match self {
+            Format::Ron => ron::de::from_bytes(input)?,
+            Format::Json => serde_json::from_slice(input)?,
+            Format::Yaml => serde_yaml::from_slice(input)?,
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use anyhow::Result;
+
+    use super::*;
+    use trilogy_save_editor::save_game::SaveKind;
+
+    #[test]
+    fn json_yaml_round_trip() -> Result<()> {
+        let files = ["test/ME1LeSave.pcsav", "test/ME2LeSave.pcsav", "test/ME3Save360.xbsav"];
+
+        for file in files {
+            let path = Path::new(file);
+            let input = fs::read(path)?;
+
+            let save_game = SaveKind::deserialize(input)?;
+            let expected = save_game.serialize(path)?;
+
+            for format in [Format::Json, Format::Yaml] {
+                let dump = format.serialize(&save_game)?;
+                let save_game: SaveKind = format.deserialize(dump.as_bytes())?;
+                let output = save_game.serialize(path)?;
+
+                // Check text round trip = binary round trip
+                assert!(expected == output, "{file} ({})", format.extension());
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,76 +1,176 @@
 use std::{
-    ffi::OsString,
     fs,
     path::{Path, PathBuf},
 };
 
-use anyhow::{Context, Result};
-use clap::Args;
-use ron::ser::PrettyConfig;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use trilogy_save_editor::{
+    head_morph::{
+        blend::blend,
+        compat::{Gender, HeadMorphDb, Severity},
+        diff::{HeadMorphDiff, ParamChange},
+        gibbed::{self, GibbedGame},
+        mesh::{LodMap, Mesh},
+    },
+    plot_db::PlotGame,
+    save_data::{
+        mass_effect_1_le::legacy::ClassSizes,
+        shared::{
+            appearance::{HeadMorph, LinearColor},
+            Vector3D,
+        },
+    },
+    save_game::{SaveGame, SaveKind},
+};
+
+use super::Format;
 
-use crate::{save_data::shared::appearance::HeadMorph, save_game::SaveKind, unreal};
+// Only ME1 saves can have no head morph at all, it is read from objects of their player package
+const NO_ME1_APPEARANCE: &str = "The Mass Effect 1 player package lacks the expected appearance objects";
 
 #[derive(Args)]
 pub struct ImportHeadMorph {
     /// Save path
     save: PathBuf,
-    /// Head Morph path (.ron, .me2headmorph, .me3headmorph)
+    /// Head Morph path (.ron, .json, .yaml, .me2headmorph, .me3headmorph)
     #[arg(short, long, value_name = "HEAD_MORPH")]
     input: PathBuf,
     /// New save path
     #[arg(short, long, value_name = "NEW_SAVE")]
     output: PathBuf,
+    /// Head Morph format (guessed from the extension by default)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+    /// Rename the meshes and textures of a head morph made for this game (me1, me2, me3) to their
+    /// names in the game of the save
+    #[arg(long, value_name = "GAME")]
+    convert_from: Option<PlotGame>,
+    /// Import the head morph even if it does not match the game of the save
+    #[arg(long)]
+    force: bool,
+    /// Directory with a custom head morph database (head_morph_db.ron)
+    #[arg(short, long, value_name = "DIR")]
+    database_dir: Option<PathBuf>,
 }
 
 impl ImportHeadMorph {
-    pub fn execute(self) -> Result<()> {
-        let ImportHeadMorph { save, input, output } = self;
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ImportHeadMorph {
+            save,
+            input,
+            output,
+            format,
+            convert_from,
+            force,
+            database_dir,
+        } = self;
+
+        let format = format.or_else(|| Format::from_path(&input)).unwrap_or_default();
 
         let head_morph_file = fs::read(input).context("Failed to open the head morph")?;
         let save_file = fs::read(save).context("Failed to open the save file")?;
 
-        let head_morph = deserialize_head_morph(head_morph_file).context("Failed to parse the head morph")?;
-        let mut save_game = SaveKind::deserialize(save_file).context("Failed to parse the save file")?;
+        let mut head_morph =
+            deserialize_head_morph(head_morph_file, format).context("Failed to parse the head morph")?;
+        let mut save_game =
+            SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
 
-        match save_game {
-            SaveKind::MassEffect1Le(ref mut me1le) => me1le.save_data.player.head_morph = Some(head_morph),
-            SaveKind::MassEffect1LePs4(ref mut me1le) => me1le.player.head_morph = Some(head_morph),
-            SaveKind::MassEffect2(ref mut me2) => me2.player.appearance.head_morph = Some(head_morph),
-            SaveKind::MassEffect2Le(ref mut me2le) => me2le.player.appearance.head_morph = Some(head_morph),
-            SaveKind::MassEffect3(ref mut me3) => me3.player.appearance.head_morph = Some(head_morph),
+        let game = save_game.game();
+        let db = HeadMorphDb::load(database_dir.as_deref())?;
+        if let Some(from) = convert_from {
+            for (old_name, new_name) in db.convert(&mut head_morph, from, game) {
+                println!("{old_name} -> {new_name}");
+            }
         }
 
-        let new_save = save_game.serialize(&output)?;
-
-        // Backup if file exists
-        if output.exists() {
-            let ext = output
-                .extension()
-                .map(|ext| {
-                    let mut ext = ext.to_owned();
-                    ext.push(".bak");
-                    ext
-                })
-                .unwrap_or_else(|| OsString::from("bak"));
-
-            let to = Path::with_extension(&output, ext);
-            fs::copy(&output, to)?;
+        if let Some(is_female) = save_game.is_female() {
+            let gender = Gender::from_is_female(is_female);
+            let issues = db.check(&head_morph, game, gender);
+            for issue in &issues {
+                println!("{issue}");
+            }
+            if !force && issues.iter().any(|issue| issue.severity == Severity::Error) {
+                bail!("This head morph does not match the {game} ({gender}) heads, use --force to import it anyway");
+            }
         }
-        fs::write(output, new_save).context("Failed to write the new save file")?;
 
-        Ok(())
+        save_game
+            .set_head_morph(Some(head_morph))
+            .context("Failed to replace the head morph of the save")?;
+
+        super::write_save(&save_game, &output)
     }
 }
 
-fn deserialize_head_morph(file: Vec<u8>) -> Result<HeadMorph> {
-    let result = if file.starts_with(b"GIBBEDMASSEFFECT2HEADMORPH") || file.starts_with(b"GIBBEDMASSEFFECT3HEADMORPH") {
-        // Gibbed's head morph
-        unreal::Deserializer::from_bytes(&file[31..])?
+fn deserialize_head_morph(file: Vec<u8>, format: Format) -> Result<HeadMorph> {
+    if GibbedGame::detect(&file).is_some() {
+        gibbed::deserialize(&file)
     } else {
         // TSE head morph
-        ron::de::from_bytes(&file)?
-    };
-    Ok(result)
+        format.deserialize(&file)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HeadMorphFormat {
+    Ron,
+    Json,
+    Yaml,
+    /// Gibbed's Mass Effect 2 save editor
+    Me2headmorph,
+    /// Gibbed's Mass Effect 3 save editor
+    Me3headmorph,
+}
+
+impl HeadMorphFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let format = match Format::from_path(path) {
+            Some(Format::Ron) => HeadMorphFormat::Ron,
+            Some(Format::Json) => HeadMorphFormat::Json,
+            Some(Format::Yaml) => HeadMorphFormat::Yaml,
+            None => {
+                let ext = path.extension()?;
+                if ext.eq_ignore_ascii_case(GibbedGame::Me2.extension()) {
+                    HeadMorphFormat::Me2headmorph
+                } else if ext.eq_ignore_ascii_case(GibbedGame::Me3.extension()) {
+                    HeadMorphFormat::Me3headmorph
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some(format)
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            HeadMorphFormat::Ron => Format::Ron.extension(),
+            HeadMorphFormat::Json => Format::Json.extension(),
+            HeadMorphFormat::Yaml => Format::Yaml.extension(),
+            HeadMorphFormat::Me2headmorph => GibbedGame::Me2.extension(),
+            HeadMorphFormat::Me3headmorph => GibbedGame::Me3.extension(),
+        }
+    }
+
+    fn gibbed_game(self) -> Option<GibbedGame> {
+        match self {
+            HeadMorphFormat::Ron | HeadMorphFormat::Json | HeadMorphFormat::Yaml => None,
+            HeadMorphFormat::Me2headmorph => Some(GibbedGame::Me2),
+            HeadMorphFormat::Me3headmorph => Some(GibbedGame::Me3),
+        }
+    }
+
+    fn serialize(self, head_morph: &HeadMorph) -> Result<Vec<u8>> {
+        let output = match self {
+            HeadMorphFormat::Ron => Format::Ron.serialize(head_morph)?.into_bytes(),
+            HeadMorphFormat::Json => Format::Json.serialize(head_morph)?.into_bytes(),
+            HeadMorphFormat::Yaml => Format::Yaml.serialize(head_morph)?.into_bytes(),
+            HeadMorphFormat::Me2headmorph => gibbed::serialize(head_morph, GibbedGame::Me2)?,
+            HeadMorphFormat::Me3headmorph => gibbed::serialize(head_morph, GibbedGame::Me3)?,
+        };
+        Ok(output)
+    }
 }
 
 #[derive(Args)]
@@ -80,44 +180,403 @@ pub struct ExportHeadMorph {
     /// Head Morph path
     #[arg(short, long, value_name = "HEAD_MORPH")]
     output: PathBuf,
+    /// Head Morph format (guessed from the extension by default, RON otherwise)
+    #[arg(short, long, value_enum)]
+    format: Option<HeadMorphFormat>,
 }
 
 impl ExportHeadMorph {
-    pub fn execute(self) -> Result<()> {
-        let ExportHeadMorph { save, mut output } = self;
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ExportHeadMorph {
+            save,
+            mut output,
+            format,
+        } = self;
+
+        let format = format
+            .or_else(|| HeadMorphFormat::from_path(&output))
+            .unwrap_or(HeadMorphFormat::Ron);
+        output = Path::with_extension(&output, format.extension());
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        // Gibbed's editors only read the head morphs of their own game
+        if let Some(gibbed_game) = format.gibbed_game() {
+            let game = save_game.game();
+            if gibbed_game.game() != game {
+                bail!(
+                    "A .{} head morph is for {} saves, this is a {game} save",
+                    gibbed_game.extension(),
+                    gibbed_game.game()
+                );
+            }
+        }
+
+        let head_morph = save_game.head_morph().context(NO_ME1_APPEARANCE)?;
+        let head_morph = Option::as_ref(&head_morph).context("This save file use the default Shepard (no head morph)")?;
+
+        let new_head_morph = format.serialize(head_morph)?;
+
+        super::backup(&output)?;
+        fs::write(output, new_head_morph).context("Failed to write the head morph")?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum HeadMeshFormat {
+    /// Wavefront OBJ
+    Obj,
+    /// Binary glTF
+    Glb,
+}
+
+impl HeadMeshFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?;
+        if ext.eq_ignore_ascii_case("obj") {
+            Some(HeadMeshFormat::Obj)
+        } else if ext.eq_ignore_ascii_case("glb") {
+            Some(HeadMeshFormat::Glb)
+        } else {
+            None
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            HeadMeshFormat::Obj => "obj",
+            HeadMeshFormat::Glb => "glb",
+        }
+    }
+}
+
+fn lod_vertices(head_morph: &HeadMorph, lod: usize) -> Result<&Vec<Vector3D>> {
+    let vertices = match lod {
+        0 => &head_morph.lod0_vertices,
+        1 => &head_morph.lod1_vertices,
+        2 => &head_morph.lod2_vertices,
+        3 => &head_morph.lod3_vertices,
+        _ => bail!("There are only 4 LODs (0 to 3)"),
+    };
+    Ok(vertices)
+}
+
+#[derive(Args)]
+pub struct ExportHeadMesh {
+    /// Head morph or save path
+    input: PathBuf,
+    /// Mesh path
+    #[arg(short, long, value_name = "MESH")]
+    output: PathBuf,
+    /// Mesh format (guessed from the extension by default, OBJ otherwise)
+    #[arg(short, long, value_enum)]
+    format: Option<HeadMeshFormat>,
+    /// Level of detail exported (0 to 3)
+    #[arg(short, long, default_value_t = 0)]
+    lod: usize,
+    /// OBJ of the base head whose faces are reused, e.g. extracted from the game files. Head morphs
+    /// only store vertex positions, without it the mesh is a point cloud
+    #[arg(long, value_name = "OBJ")]
+    faces: Option<PathBuf>,
+}
+
+impl ExportHeadMesh {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ExportHeadMesh {
+            input,
+            mut output,
+            format,
+            lod,
+            faces,
+        } = self;
+
+        let format = format
+            .or_else(|| HeadMeshFormat::from_path(&output))
+            .unwrap_or(HeadMeshFormat::Obj);
+        output = Path::with_extension(&output, format.extension());
+
+        let head_morph = load_head_morph(&input, class_sizes)?;
+        let vertices = lod_vertices(&head_morph, lod)?;
+        if vertices.is_empty() {
+            bail!("LOD{lod} of this head morph has no vertices");
+        }
+
+        let faces = match faces {
+            Some(path) => {
+                let file = fs::read_to_string(&path).with_context(|| format!("Failed to open `{}`", path.display()))?;
+                let base = Mesh::from_obj(&file).with_context(|| format!("Failed to parse `{}`", path.display()))?;
+                if base.vertices.len() != vertices.len() {
+                    bail!(
+                        "`{}` has {} vertices, LOD{lod} of this head morph has {}",
+                        path.display(),
+                        base.vertices.len(),
+                        vertices.len()
+                    );
+                }
+                base.faces
+            }
+            None => Vec::new(),
+        };
+        let mesh = Mesh::new(vertices.clone(), faces)?;
+
+        let new_mesh = match format {
+            HeadMeshFormat::Obj => mesh.to_obj().into_bytes(),
+            HeadMeshFormat::Glb => mesh.to_glb()?,
+        };
+
+        super::backup(&output)?;
+        fs::write(output, new_mesh).context("Failed to write the mesh")?;
+
+        Ok(())
+    }
+}
 
-        output = Path::with_extension(&output, "ron");
+#[derive(Args)]
+pub struct ImportHeadMesh {
+    /// Save path, with the head morph the mesh was exported from
+    save: PathBuf,
+    /// OBJ path, its vertices must be in the order of the head morph (e.g. exported with
+    /// `export-head-mesh` and edited without adding or removing vertices)
+    #[arg(short, long, value_name = "OBJ")]
+    input: PathBuf,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+    /// RON file regenerating lod1 to lod3 from the new lod0, each LOD is a list of lod0 vertex indices,
+    /// e.g. `(lod1: [0, 2, 5], lod2: [0, 5], lod3: [5])`
+    ///
+    /// No map is bundled, without one lod1 to lod3 are kept as they were in the save
+    #[arg(long, value_name = "LOD_MAP")]
+    lod_map: Option<PathBuf>,
+}
+
+impl ImportHeadMesh {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ImportHeadMesh {
+            save,
+            input,
+            output,
+            lod_map,
+        } = self;
 
+        let mesh_file = fs::read_to_string(&input).context("Failed to open the mesh")?;
         let save_file = fs::read(save).context("Failed to open the save file")?;
-        let save_game = SaveKind::deserialize(save_file).context("Failed to parse the save file")?;
-
-        let head_morph = match save_game {
-            SaveKind::MassEffect1Le(ref me1le) => &me1le.save_data.player.head_morph,
-            SaveKind::MassEffect1LePs4(ref me1le) => &me1le.player.head_morph,
-            SaveKind::MassEffect2(ref me2) => &me2.player.appearance.head_morph,
-            SaveKind::MassEffect2Le(ref me2le) => &me2le.player.appearance.head_morph,
-            SaveKind::MassEffect3(ref me3) => &me3.player.appearance.head_morph,
-        }
-        .as_ref()
-        .context("This save file use the default Shepard (no head morph)")?;
-
-        let pretty_config = PrettyConfig::new().enumerate_arrays(true).new_line(String::from('\n'));
-        let new_head_morph = ron::ser::to_string_pretty(head_morph, pretty_config)?;
-
-        // Backup if file exists
-        if output.exists() {
-            let ext = output
-                .extension()
-                .map(|ext| {
-                    let mut ext = ext.to_owned();
-                    ext.push(".bak");
-                    ext
-                })
-                .unwrap_or_else(|| OsString::from("bak"));
-
-            let to = Path::with_extension(&output, ext);
-            fs::copy(&output, to)?;
+
+        let mesh = Mesh::from_obj(&mesh_file).context("Failed to parse the mesh")?;
+        let lod_map = match lod_map {
+            Some(path) => {
+                let file = fs::read_to_string(path).context("Failed to open the LOD map")?;
+                Some(LodMap::from_ron(&file).context("Failed to parse the LOD map")?)
+            }
+            None => None,
+        };
+        let mut save_game =
+            SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let mut head_morph = save_game
+            .head_morph()
+            .context(NO_ME1_APPEARANCE)?
+            .into_owned()
+            .context("This save file use the default Shepard (no head morph)")?;
+
+        if mesh.vertices.len() != head_morph.lod0_vertices.len() {
+            bail!(
+                "The mesh has {} vertices, lod0 of the head morph has {}",
+                mesh.vertices.len(),
+                head_morph.lod0_vertices.len()
+            );
+        }
+        head_morph.lod0_vertices = mesh.vertices;
+
+        if let Some(lod_map) = lod_map {
+            for lod in lod_map.apply(&mut head_morph)? {
+                println!("LOD{lod} regenerated");
+            }
+        }
+        save_game
+            .set_head_morph(Some(head_morph))
+            .context("Failed to replace the head morph of the save")?;
+
+        super::write_save(&save_game, &output)
+    }
+}
+
+// A head morph file, or the head morph of a save
+fn load_head_morph(path: &Path, class_sizes: &ClassSizes) -> Result<HeadMorph> {
+    let file = fs::read(path).with_context(|| format!("Failed to open `{}`", path.display()))?;
+
+    let is_head_morph = Format::from_path(path).is_some() || GibbedGame::detect(&file).is_some();
+    if is_head_morph {
+        let format = Format::from_path(path).unwrap_or_default();
+        return deserialize_head_morph(file, format)
+            .with_context(|| format!("Failed to parse the head morph `{}`", path.display()));
+    }
+
+    let save_game = SaveKind::deserialize_with(file, class_sizes)
+        .with_context(|| format!("Failed to parse the save file `{}`", path.display()))?;
+    save_game
+        .head_morph()
+        .context(NO_ME1_APPEARANCE)?
+        .into_owned()
+        .with_context(|| format!("`{}` use the default Shepard (no head morph)", path.display()))
+}
+
+#[derive(Args)]
+pub struct HeadMorphTools {
+    #[command(subcommand)]
+    command: HeadMorphCommand,
+}
+
+#[derive(Subcommand)]
+enum HeadMorphCommand {
+    /// Compare two head morphs, from head morph files or saves
+    Diff(DiffHeadMorph),
+    /// Weighted blend of head morphs, from head morph files or saves
+    Blend(BlendHeadMorph),
+}
+
+impl HeadMorphTools {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        match self.command {
+            HeadMorphCommand::Diff(diff) => diff.execute(class_sizes),
+            HeadMorphCommand::Blend(blend) => blend.execute(class_sizes),
         }
+    }
+}
+
+#[derive(Args)]
+struct DiffHeadMorph {
+    /// Old head morph or save path
+    old: PathBuf,
+    /// New head morph or save path
+    new: PathBuf,
+    /// Number of most moved vertices printed per LOD
+    #[arg(short, long, default_value_t = 10)]
+    top: usize,
+}
+
+fn print_changes<T>(label: &str, changes: &[ParamChange<T>], display: impl Fn(&T) -> String) {
+    let display = |value: &Option<T>| value.as_ref().map(&display).unwrap_or_else(|| "none".to_owned());
+    for ParamChange { name, old, new } in changes {
+        println!("{label} `{name}`: {} -> {}", display(old), display(new));
+    }
+}
+
+impl DiffHeadMorph {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let DiffHeadMorph { old, new, top } = self;
+
+        let old = load_head_morph(&old, class_sizes)?;
+        let new = load_head_morph(&new, class_sizes)?;
+        let HeadMorphDiff {
+            hair_mesh,
+            added_accessories,
+            removed_accessories,
+            morph_features,
+            offset_bones,
+            scalar_parameters,
+            vector_parameters,
+            texture_parameters,
+            lods,
+        } = HeadMorphDiff::new(&old, &new, top);
+
+        if let Some((old, new)) = hair_mesh {
+            println!("Hair mesh: {old} -> {new}");
+        }
+        for mesh in added_accessories {
+            println!("Accessory mesh: + {mesh}");
+        }
+        for mesh in removed_accessories {
+            println!("Accessory mesh: - {mesh}");
+        }
+
+        let vector = |Vector3D { x, y, z }: &Vector3D| format!("({x}, {y}, {z})");
+        let color = |LinearColor { r, g, b, a }: &LinearColor| format!("({r}, {g}, {b}, {a})");
+        print_changes("Morph feature", &morph_features, f32::to_string);
+        print_changes("Offset bone", &offset_bones, vector);
+        print_changes("Scalar parameter", &scalar_parameters, f32::to_string);
+        print_changes("Vector parameter", &vector_parameters, color);
+        print_changes("Texture parameter", &texture_parameters, String::to_owned);
+
+        for lod in lods.iter().filter(|lod| lod.old_len > 0 || lod.new_len > 0) {
+            if lod.old_len != lod.new_len {
+                println!("LOD{}: {} -> {} vertices", lod.lod, lod.old_len, lod.new_len);
+            }
+            let compared = lod.old_len.min(lod.new_len);
+            print!(
+                "LOD{}: {} of {compared} vertices moved, max {}, mean {}",
+                lod.lod, lod.moved, lod.max, lod.mean
+            );
+            if !lod.most_moved.is_empty() {
+                let most_moved: Vec<_> = lod
+                    .most_moved
+                    .iter()
+                    .map(|(idx, moved)| format!("{idx} ({moved})"))
+                    .collect();
+                print!(", most moved: {}", most_moved.join(", "));
+            }
+            println!();
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct BlendHeadMorph {
+    /// Head morph or save paths
+    #[arg(required = true, num_args = 2..)]
+    inputs: Vec<PathBuf>,
+    /// Weight of each input, in the same order, e.g. `-w 3 -w 1` (equal weights by default)
+    #[arg(short, long, value_name = "WEIGHT")]
+    weight: Vec<f32>,
+    /// Input the hair, the accessories and the textures are taken from, starting at 1
+    #[arg(short, long, default_value_t = 1, value_name = "INPUT")]
+    source: usize,
+    /// Head Morph path
+    #[arg(short, long, value_name = "HEAD_MORPH")]
+    output: PathBuf,
+    /// Head Morph format (guessed from the extension by default, RON otherwise)
+    #[arg(short, long, value_enum)]
+    format: Option<HeadMorphFormat>,
+}
+
+impl BlendHeadMorph {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let BlendHeadMorph {
+            inputs,
+            mut weight,
+            source,
+            mut output,
+            format,
+        } = self;
+
+        if weight.is_empty() {
+            weight = vec![1.0; inputs.len()];
+        } else if weight.len() != inputs.len() {
+            bail!("{} weight(s) for {} head morphs", weight.len(), inputs.len());
+        }
+        if source == 0 || source > inputs.len() {
+            bail!("--source must be between 1 and {}", inputs.len());
+        }
+
+        let format = format
+            .or_else(|| HeadMorphFormat::from_path(&output))
+            .unwrap_or(HeadMorphFormat::Ron);
+        output = Path::with_extension(&output, format.extension());
+
+        let head_morphs = inputs
+            .iter()
+            .map(|path| load_head_morph(path, class_sizes))
+            .collect::<Result<Vec<_>>>()?;
+        let weighted: Vec<_> = head_morphs.iter().zip(weight).collect();
+
+        let new_head_morph = format.serialize(&blend(&weighted, source - 1)?)?;
+
+        super::backup(&output)?;
         fs::write(output, new_head_morph).context("Failed to write the head morph")?;
 
         Ok(())
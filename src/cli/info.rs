@@ -0,0 +1,241 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use trilogy_save_editor::{
+    save_data::{
+        mass_effect_1_le::{legacy::ClassSizes, Me1LeSaveData},
+        shared::{
+            player::{Notoriety, Origin},
+            EndGameState, SaveTimeStamp,
+        },
+    },
+    save_game::{SaveGame, SaveKind},
+};
+
+#[derive(Args)]
+pub struct Info {
+    /// Save path
+    save: PathBuf,
+}
+
+impl Info {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Info { save } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let summary = Summary::new(&save_game);
+        summary.print();
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Summary<'a> {
+    game: &'static str,
+    platform: &'static str,
+    name: Option<&'a str>,
+    class: Option<String>,
+    level: Option<i32>,
+    credits: Option<i32>,
+    current_xp: Option<f32>,
+    origin: Option<Origin>,
+    notoriety: Option<Notoriety>,
+    difficulty: Option<String>,
+    end_game_state: Option<EndGameState>,
+    seconds_played: Option<f32>,
+    timestamp: Option<&'a SaveTimeStamp>,
+    map: &'a str,
+    squad: Vec<&'a str>,
+    head_morph: Option<bool>,
+}
+
+impl<'a> Summary<'a> {
+    fn new(save_game: &'a SaveKind) -> Self {
+        fn me1_le<'a>(game: &'static str, platform: &'static str, save_data: &'a Me1LeSaveData) -> Summary<'a> {
+            let player = &save_data.player;
+            Summary {
+                game,
+                platform,
+                class: Some(format!("{:?}", player.player_class)),
+                current_xp: Some(player.current_xp),
+                origin: Some(player.origin),
+                notoriety: Some(player.notoriety),
+                seconds_played: Some(save_data.seconds_played as f32),
+                map: &save_data.base_level_name,
+                ..Default::default()
+            }
+        }
+
+        macro_rules! me2_me3 {
+            ($game:expr, $platform:expr, $save_game:expr) => {{
+                let save_game = $save_game;
+                let player = &save_game.player;
+                Summary {
+                    game: $game,
+                    platform: $platform,
+                    class: Some(player.class_name.clone()),
+                    current_xp: Some(player.current_xp),
+                    origin: Some(player.origin),
+                    notoriety: Some(player.notoriety),
+                    end_game_state: Some(save_game.end_game_state),
+                    seconds_played: Some(save_game.seconds_played),
+                    map: &save_game.base_level_name,
+                    ..Default::default()
+                }
+            }};
+        }
+
+        fn platform(is_xbox360: bool) -> &'static str {
+            if is_xbox360 {
+                "Xbox 360"
+            } else {
+                "PC"
+            }
+        }
+
+        let summary = match save_game {
+            SaveKind::MassEffect1(me1) => Summary {
+                game: "Mass Effect 1",
+                platform: "PC",
+                class: me1.player.class(),
+                current_xp: me1.player.current_xp(),
+                origin: me1.player.origin(),
+                notoriety: me1.player.notoriety(),
+                seconds_played: me1.player.seconds_played(),
+                map: &me1.state.base_level_name,
+                ..Default::default()
+            },
+            SaveKind::MassEffect1Le(me1le) => me1_le("Mass Effect 1 Legendary", "PC", &me1le.save_data),
+            SaveKind::MassEffect1LePs4(me1le) => me1_le("Mass Effect 1 Legendary", "PS4", me1le),
+            SaveKind::MassEffect2(me2) => me2_me3!("Mass Effect 2", platform(me2.is_xbox360()), me2),
+            SaveKind::MassEffect2Le(me2le) => me2_me3!("Mass Effect 2 Legendary", "PC", me2le),
+            SaveKind::MassEffect3(me3) => me2_me3!("Mass Effect 3", platform(me3.is_xbox360()), me3),
+        };
+
+        // Common to every game
+        Summary {
+            name: save_game.player_name(),
+            level: save_game.level(),
+            credits: save_game.credits(),
+            difficulty: save_game.difficulty(),
+            timestamp: save_game.timestamp(),
+            squad: save_game.squad(),
+            head_morph: save_game.head_morph().map(|head_morph| head_morph.is_some()),
+            ..summary
+        }
+    }
+
+    fn print(&self) {
+        let Summary {
+            game,
+            platform,
+            name,
+            ref class,
+            level,
+            credits,
+            current_xp,
+            origin,
+            notoriety,
+            ref difficulty,
+            end_game_state,
+            seconds_played,
+            timestamp,
+            map,
+            ref squad,
+            head_morph,
+        } = *self;
+
+        println!("Game: {game}");
+        println!("Platform: {platform}");
+        if let Some(name) = name {
+            println!("Name: {name}");
+        }
+        if let Some(class) = class {
+            println!("Class: {class}");
+        }
+        if let Some(level) = level {
+            println!("Level: {level}");
+        }
+        if let Some(credits) = credits {
+            println!("Credits: {credits}");
+        }
+        if let Some(current_xp) = current_xp {
+            println!("XP: {current_xp}");
+        }
+        if let Some(origin) = origin {
+            println!("Origin: {origin:?}");
+        }
+        if let Some(notoriety) = notoriety {
+            println!("Notoriety: {notoriety:?}");
+        }
+        if let Some(difficulty) = difficulty {
+            println!("Difficulty: {difficulty}");
+        }
+        if let Some(end_game_state) = end_game_state {
+            println!("End game state: {end_game_state:?}");
+        }
+        if let Some(seconds_played) = seconds_played {
+            let seconds_played = seconds_played as u64;
+            let (hours, minutes, seconds) = (seconds_played / 3600, seconds_played % 3600 / 60, seconds_played % 60);
+            println!("Time played: {hours}:{minutes:02}:{seconds:02} ({seconds_played} seconds)");
+        }
+        if let Some(timestamp) = timestamp {
+            println!("Saved on: {timestamp}");
+        }
+        println!("Map: {map}");
+        if !squad.is_empty() {
+            println!("Squad: {}", squad.join(", "));
+        }
+        if let Some(head_morph) = head_morph {
+            println!("Custom head morph: {}", if head_morph { "yes" } else { "no" });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn summary() -> Result<()> {
+        let files = [
+            ("test/ME1Save.MassEffectSave", "Mass Effect 1", "PC"),
+            ("test/ME1LeSave.pcsav", "Mass Effect 1 Legendary", "PC"),
+            ("test/ME2Save.pcsav", "Mass Effect 2", "PC"),
+            ("test/ME2Save360.xbsav", "Mass Effect 2", "Xbox 360"),
+            ("test/ME2LeSave.pcsav", "Mass Effect 2 Legendary", "PC"),
+            ("test/ME3Save.pcsav", "Mass Effect 3", "PC"),
+            ("test/ME3Save360.xbsav", "Mass Effect 3", "Xbox 360"),
+        ];
+
+        for (file, game, platform) in files {
+            let save_game = SaveKind::deserialize(fs::read(file)?)?;
+            let summary = Summary::new(&save_game);
+            assert!(summary.game == game && summary.platform == platform, "{file}");
+            assert!(summary.name.is_some() && summary.level.is_some() && summary.credits.is_some(), "{file}");
+            assert!(summary.class.is_some() && summary.current_xp.is_some(), "{file}");
+            assert!(summary.origin.is_some() && summary.notoriety.is_some(), "{file}");
+            assert!(summary.difficulty.is_some() && summary.seconds_played.is_some(), "{file}");
+            assert!(!summary.map.is_empty() && !summary.squad.is_empty(), "{file}");
+            assert_eq!(summary.head_morph, Some(true), "{file}");
+        }
+
+        // ME1 and ME1LE difficulties are stored as an index
+        for file in ["test/ME1Save.MassEffectSave", "test/ME1LeSave.pcsav"] {
+            let save_game = SaveKind::deserialize(fs::read(file)?)?;
+            let summary = Summary::new(&save_game);
+            assert_eq!(summary.difficulty.as_deref(), Some("Insanity"), "{file}");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Error, Result};
+use clap::Args;
+
+use trilogy_save_editor::{
+    reflect::FieldPath,
+    save_data::mass_effect_1_le::legacy::ClassSizes,
+    save_game::SaveKind,
+    save_merge::{self, Side},
+};
+
+#[derive(Args)]
+pub struct Merge {
+    /// Save path, the fields that are not merged or taken come from this save ("ours")
+    save: PathBuf,
+    /// Common ancestor of SAVE and --theirs, for a three-way merge of the plot, the journal and the codex
+    #[arg(long, requires = "theirs", value_name = "BASE_SAVE")]
+    base: Option<PathBuf>,
+    /// Save merged into SAVE
+    #[arg(long, requires = "base", value_name = "THEIRS_SAVE")]
+    theirs: Option<PathBuf>,
+    /// Save that wins the conflicts of the three-way merge (ours, theirs)
+    #[arg(long, default_value_t)]
+    prefer: Side,
+    /// Take a whole field from another save, e.g. `--take plot=X.pcsav --take player=Y.pcsav`
+    #[arg(short, long, value_name = "PATH=SAVE")]
+    take: Vec<Take>,
+    /// Directory with custom plot databases (me1_plot_db.ron, me2_plot_db.ron, me3_plot_db.ron)
+    #[arg(short, long, value_name = "DIR")]
+    database_dir: Option<PathBuf>,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+}
+
+#[derive(Clone)]
+struct Take {
+    path: FieldPath,
+    save: PathBuf,
+}
+
+impl FromStr for Take {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (path, save) = s.split_once('=').context("Expected PATH=SAVE")?;
+        Ok(Take {
+            path: path.parse()?,
+            save: PathBuf::from(save),
+        })
+    }
+}
+
+fn load(path: &Path, class_sizes: &ClassSizes) -> Result<SaveKind> {
+    let save_file = fs::read(path).with_context(|| format!("Failed to open `{}`", path.display()))?;
+    SaveKind::deserialize_with(save_file, class_sizes).with_context(|| format!("Failed to parse `{}`", path.display()))
+}
+
+impl Merge {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Merge {
+            save,
+            base,
+            theirs,
+            prefer,
+            take,
+            database_dir,
+            output,
+        } = self;
+
+        if theirs.is_none() && take.is_empty() {
+            bail!("Nothing to merge, use --base and --theirs or --take");
+        }
+
+        let mut save_game = load(&save, class_sizes)?;
+
+        if let Some((base, theirs)) = base.zip(theirs) {
+            let conflicts = save_merge::merge(
+                &load(&base, class_sizes)?,
+                &mut save_game,
+                &load(&theirs, class_sizes)?,
+                prefer,
+                database_dir.as_deref(),
+            )?;
+
+            for conflict in &conflicts.plot {
+                println!("Conflict: {conflict}");
+            }
+            for conflict in &conflicts.fields {
+                let display = |value: &Option<_>| match value {
+                    Some(value) => super::display(value),
+                    None => Ok("absent".to_owned()),
+                };
+                println!(
+                    "Conflict: {}: base {}, ours {}, theirs {}",
+                    conflict.path,
+                    display(&conflict.base)?,
+                    display(&conflict.ours)?,
+                    display(&conflict.theirs)?
+                );
+            }
+            if !conflicts.is_empty() {
+                println!("{} conflict(s), resolved with {prefer}", conflicts.len());
+            }
+        }
+
+        for Take { path, save } in take {
+            save_merge::take(&mut save_game, &load(&save, class_sizes)?, &path)?;
+            println!("{path} taken from `{}`", save.display());
+        }
+
+        super::write_save(&save_game, &output)
+    }
+}
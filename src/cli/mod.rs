@@ -1,3 +1,106 @@
+mod convert;
+mod diff;
+mod edit;
+mod field;
+mod format;
 mod head_morph;
+mod info;
+mod merge;
+mod plot;
+mod save;
+mod verify;
 
-pub use self::head_morph::*;
+pub use self::{
+    convert::*, diff::*, edit::*, field::*, format::*, head_morph::*, info::*, merge::*, plot::*, save::*, verify::*,
+};
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Error, Result};
+use clap::Args;
+use serde_yaml::Value;
+use trilogy_save_editor::{save_data::mass_effect_1_le::legacy::ClassSizes, save_game::SaveKind};
+
+// Options of every command that reads saves
+#[derive(Args)]
+pub struct LoadOptions {
+    /// Size of an ME1LE legacy object class unknown to the editor (DLC, mods)
+    ///
+    /// In bytes, after the owner, e.g. `--legacy-class BioModdedSaveObject=17`. Without it a save with
+    /// such an object keeps all its legacy objects as raw bytes.
+    #[arg(long = "legacy-class", value_name = "CLASS=SIZE", global = true)]
+    legacy_classes: Vec<LegacyClass>,
+}
+
+impl LoadOptions {
+    pub fn class_sizes(&self) -> ClassSizes {
+        self.legacy_classes.iter().map(|class| (class.name.clone(), class.size)).collect()
+    }
+}
+
+#[derive(Clone)]
+struct LegacyClass {
+    name: String,
+    size: usize,
+}
+
+impl FromStr for LegacyClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, size) = s.split_once('=').context("Expected CLASS=SIZE")?;
+        Ok(LegacyClass {
+            name: name.to_owned(),
+            size: size.parse().context("The size must be a number of bytes")?,
+        })
+    }
+}
+
+// Backup if file exists
+fn backup(path: &Path) -> Result<()> {
+    if path.exists() {
+        let ext = path
+            .extension()
+            .map(|ext| {
+                let mut ext = ext.to_owned();
+                ext.push(".bak");
+                ext
+            })
+            .unwrap_or_else(|| OsString::from("bak"));
+
+        let to: PathBuf = Path::with_extension(path, ext);
+        fs::copy(path, to)?;
+    }
+    Ok(())
+}
+
+// Serialize with the checksum of the output platform, after a backup
+fn write_save(save_game: &SaveKind, output: &Path) -> Result<()> {
+    let new_save = save_game.serialize(output)?;
+    write_new_save(&new_save, output)
+}
+
+// An already serialized save, after a backup
+fn write_new_save(new_save: &[u8], output: &Path) -> Result<()> {
+    backup(output)?;
+    fs::write(output, new_save).context("Failed to write the new save file")?;
+
+    Ok(())
+}
+
+// Scalars on a single line, lists and structs as YAML
+fn display(value: &Value) -> Result<String> {
+    let display = match value {
+        Value::Null => "None".to_owned(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => value.to_owned(),
+        _ => format!("\n{}", serde_yaml::to_string(value)?.trim_end()),
+    };
+    Ok(display)
+}
@@ -0,0 +1,417 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use trilogy_save_editor::{
+    plot_db::{PlotDb, PlotGame, PlotKind},
+    plot_patch::PlotPatch,
+    save_data::{
+        mass_effect_1_le::legacy::ClassSizes,
+        shared::plot::{check_plot_id, PlotAccess},
+    },
+    save_game::{SaveGame, SaveKind},
+};
+
+use super::Format;
+
+#[derive(Args)]
+pub struct Plot {
+    #[command(subcommand)]
+    command: PlotCommand,
+}
+
+#[derive(Subcommand)]
+enum PlotCommand {
+    /// List the known plot variables and their value
+    List(ListPlot),
+    /// Print a plot variable
+    Get(GetPlot),
+    /// Change a plot variable
+    Set(SetPlot),
+    /// Apply a list of plot changes, nothing is written if any expected value does not match
+    Patch(PatchPlot),
+}
+
+impl Plot {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        match self.command {
+            PlotCommand::List(list) => list.execute(class_sizes),
+            PlotCommand::Get(get) => get.execute(class_sizes),
+            PlotCommand::Set(set) => set.execute(class_sizes),
+            PlotCommand::Patch(patch) => patch.execute(class_sizes),
+        }
+    }
+}
+
+#[derive(Args)]
+struct ListPlot {
+    /// Save path
+    save: PathBuf,
+    /// Only list the variables whose name or category contains this text
+    #[arg(short, long)]
+    search: Option<String>,
+    /// Only list the variables of this game's plot table (me1, me2, me3)
+    #[arg(short, long)]
+    game: Option<PlotGame>,
+    /// Directory with custom plot databases (me1_plot_db.ron, me2_plot_db.ron, me3_plot_db.ron)
+    #[arg(short, long, value_name = "DIR")]
+    database_dir: Option<PathBuf>,
+}
+
+impl ListPlot {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ListPlot {
+            save,
+            search,
+            game,
+            database_dir,
+        } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        for (table_game, plot) in save_game.plot_tables() {
+            if game.map(|game| game != table_game).unwrap_or_default() {
+                continue;
+            }
+
+            let plot_db = PlotDb::load(table_game, database_dir.as_deref())?;
+            let variables: Box<dyn Iterator<Item = _>> = match search {
+                Some(ref search) => Box::new(plot_db.search(search)),
+                None => Box::new(plot_db.variables()),
+            };
+
+            for variable in variables {
+                let value = match variable.kind {
+                    PlotKind::Boolean => plot.boolean(variable.id).to_string(),
+                    PlotKind::Integer => plot.integer(variable.id).to_string(),
+                    PlotKind::Float => plot.float(variable.id).to_string(),
+                };
+                println!(
+                    "{table_game} {:<5} {:>6} {} / {} = {value}",
+                    variable.kind, variable.id, variable.category, variable.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct Variable {
+    /// Plot id or name
+    variable: String,
+    /// Plot table (me1, me2, me3), searched in every table of the save by default
+    #[arg(short, long)]
+    game: Option<PlotGame>,
+    /// Variable kind (bool, int, float), required with a plot id
+    #[arg(short, long)]
+    kind: Option<PlotKind>,
+    /// Directory with custom plot databases (me1_plot_db.ron, me2_plot_db.ron, me3_plot_db.ron)
+    #[arg(short, long, value_name = "DIR")]
+    database_dir: Option<PathBuf>,
+}
+
+struct ResolvedVariable {
+    table: usize,
+    game: PlotGame,
+    kind: PlotKind,
+    id: usize,
+    name: Option<String>,
+}
+
+impl Variable {
+    // Find the plot table and the id of the variable
+    fn resolve(&self, games: &[PlotGame]) -> Result<ResolvedVariable> {
+        let Variable {
+            ref variable,
+            game,
+            kind,
+            ref database_dir,
+        } = *self;
+
+        let tables: Vec<_> = games
+            .iter()
+            .enumerate()
+            .filter(|(_, table_game)| game.map(|game| game == **table_game).unwrap_or(true))
+            .map(|(table, &game)| (table, game))
+            .collect();
+
+        if tables.is_empty() {
+            bail!("This save has no {} plot table", game.unwrap());
+        }
+
+        if let Ok(id) = variable.parse::<usize>() {
+            check_plot_id(id)?;
+            let kind = kind.context("The variable kind (--kind) is required with a plot id")?;
+            // The save's own plot table if not specified
+            let (table, game) = tables[0];
+
+            let plot_db = PlotDb::load(game, database_dir.as_deref())?;
+            let name = plot_db.find(kind, id).map(|variable| variable.name.to_owned());
+
+            return Ok(ResolvedVariable {
+                table,
+                game,
+                kind,
+                id,
+                name,
+            });
+        }
+
+        for (table, game) in tables {
+            let plot_db = PlotDb::load(game, database_dir.as_deref())?;
+            let found = plot_db
+                .find_by_name(variable)
+                .filter(|found| kind.map(|kind| kind == found.kind).unwrap_or(true));
+
+            if let Some(found) = found {
+                return Ok(ResolvedVariable {
+                    table,
+                    game,
+                    kind: found.kind,
+                    id: found.id,
+                    name: Some(found.name.to_owned()),
+                });
+            }
+        }
+
+        bail!("Unknown plot variable `{variable}`")
+    }
+}
+
+impl ResolvedVariable {
+    fn value(&self, plot: &dyn PlotAccess) -> String {
+        match self.kind {
+            PlotKind::Boolean => plot.boolean(self.id).to_string(),
+            PlotKind::Integer => plot.integer(self.id).to_string(),
+            PlotKind::Float => plot.float(self.id).to_string(),
+        }
+    }
+
+    fn set_value(&self, plot: &mut dyn PlotAccess, value: &str) -> Result<()> {
+        match self.kind {
+            PlotKind::Boolean => {
+                let value = match value.to_ascii_lowercase().as_str() {
+                    "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => bail!("Invalid boolean `{value}`, expected true, false, 1 or 0"),
+                };
+                plot.set_boolean(self.id, value)?;
+            }
+            PlotKind::Integer => {
+                let value = value.parse().with_context(|| format!("Invalid integer `{value}`"))?;
+                plot.set_integer(self.id, value)?;
+            }
+            PlotKind::Float => {
+                let value = value.parse().with_context(|| format!("Invalid float `{value}`"))?;
+                plot.set_float(self.id, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let ResolvedVariable {
+            game,
+            kind,
+            id,
+            ref name,
+            ..
+        } = *self;
+
+        match name {
+            Some(name) => format!("{game} {kind} {id} ({name})"),
+            None => format!("{game} {kind} {id}"),
+        }
+    }
+}
+
+#[derive(Args)]
+struct GetPlot {
+    /// Save path
+    save: PathBuf,
+    #[command(flatten)]
+    variable: Variable,
+}
+
+impl GetPlot {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let GetPlot { save, variable } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let tables = save_game.plot_tables();
+        let games: Vec<_> = tables.iter().map(|(game, _)| *game).collect();
+        let variable = variable.resolve(&games)?;
+
+        let (_, plot) = tables[variable.table];
+        println!("{} = {}", variable.describe(), variable.value(plot));
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct SetPlot {
+    /// Save path
+    save: PathBuf,
+    #[command(flatten)]
+    variable: Variable,
+    /// New value
+    value: String,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+}
+
+impl SetPlot {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let SetPlot {
+            save,
+            variable,
+            value,
+            output,
+        } = self;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let mut save_game =
+            SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let mut tables = save_game.plot_tables_mut();
+        let games: Vec<_> = tables.iter().map(|(game, _)| *game).collect();
+        let variable = variable.resolve(&games)?;
+
+        let (_, ref mut plot) = tables[variable.table];
+        let old_value = variable.value(*plot);
+        variable.set_value(*plot, &value)?;
+        println!("{} = {old_value} -> {}", variable.describe(), variable.value(*plot));
+
+        super::write_save(&save_game, &output)
+    }
+}
+
+#[derive(Args)]
+struct PatchPlot {
+    /// Save path
+    save: PathBuf,
+    /// Patch path (.ron, .toml, .json or .yaml)
+    patch: PathBuf,
+    /// New save path
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+}
+
+impl PatchPlot {
+    fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let PatchPlot { save, patch, output } = self;
+
+        let patch_file = fs::read(&patch).context("Failed to open the patch file")?;
+        let is_toml = patch
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("toml"))
+            .unwrap_or_default();
+        let patch = if is_toml {
+            PlotPatch::from_toml(&String::from_utf8_lossy(&patch_file))
+        } else {
+            Format::from_path(&patch).unwrap_or_default().deserialize(&patch_file)
+        }
+        .context("Failed to parse the patch file")?;
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let mut save_game =
+            SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        if let Err(mismatches) = patch.apply(&mut save_game.plot_tables_mut()) {
+            for mismatch in &mismatches {
+                println!("{mismatch}");
+            }
+            bail!(
+                "{} change(s) could not be applied, the save was not written",
+                mismatches.len()
+            );
+        }
+        println!("{} change(s) applied", patch.changes.len());
+
+        super::write_save(&save_game, &output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::Path, process};
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn resolve_and_set() -> Result<()> {
+        // The ME1 table of an ME2 save, with a custom database naming one of its variables
+        let database_dir = env::temp_dir().join(format!("tse_plot_db_{}", process::id()));
+        fs::create_dir_all(&database_dir)?;
+        fs::write(
+            database_dir.join("me1_plot_db.ron"),
+            r#"(categories: [(name: "Test", booleans: { 10: "Test variable" })])"#,
+        )?;
+        let variable = |variable: &str, game, kind| Variable {
+            variable: variable.to_owned(),
+            game,
+            kind,
+            database_dir: Some(database_dir.clone()),
+        };
+
+        let mut save_game = SaveKind::deserialize(fs::read("test/ME2Save.pcsav")?)?;
+        let mut tables = save_game.plot_tables_mut();
+        let games: Vec<_> = tables.iter().map(|(game, _)| *game).collect();
+
+        let resolved = variable("test VARIABLE", None, None).resolve(&games)?;
+        assert!(resolved.game == PlotGame::Me1 && resolved.kind == PlotKind::Boolean && resolved.id == 10);
+        assert_eq!(resolved.table, 1);
+
+        let by_id = variable("10", Some(PlotGame::Me1), Some(PlotKind::Boolean)).resolve(&games)?;
+        assert_eq!(by_id.name.as_deref(), Some("Test variable"));
+        assert!(variable("Missing variable", None, None).resolve(&games).is_err());
+        assert!(variable("10", None, None).resolve(&games).is_err());
+        assert!(variable("4000000000", Some(PlotGame::Me1), Some(PlotKind::Boolean)).resolve(&games).is_err());
+
+        let (_, ref mut plot) = tables[resolved.table];
+        let value = !plot.boolean(resolved.id);
+        resolved.set_value(*plot, &value.to_string())?;
+        assert!(resolved.set_value(*plot, "maybe").is_err());
+
+        let path = Path::new("test/ME2Save.pcsav");
+        let save_game = SaveKind::deserialize(save_game.serialize(path)?)?;
+        assert_eq!(save_game.plot_tables()[1].1.boolean(10), value);
+
+        fs::remove_dir_all(&database_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_embedded() -> Result<()> {
+        let save_game = SaveKind::deserialize(fs::read("test/ME2Save.pcsav")?)?;
+        let tables = save_game.plot_tables();
+        let games: Vec<_> = tables.iter().map(|(game, _)| *game).collect();
+        let variable = |game| Variable {
+            variable: "Renegade".to_owned(),
+            game,
+            kind: None,
+            database_dir: None,
+        };
+
+        // The save's own table first
+        let resolved = variable(None).resolve(&games)?;
+        assert!(resolved.game == PlotGame::Me2 && resolved.kind == PlotKind::Integer && resolved.id == 3);
+        assert_eq!(resolved.value(tables[resolved.table].1), "10388");
+
+        // The imported ME1 table
+        let resolved = variable(Some(PlotGame::Me1)).resolve(&games)?;
+        assert!(resolved.table == 1 && resolved.id == 46);
+        assert_eq!(resolved.describe(), "me1 int 46 (Renegade)");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use trilogy_save_editor::{save_data::mass_effect_1_le::legacy::ClassSizes, save_game::SaveKind};
+
+use super::Format;
+
+#[derive(Args)]
+pub struct ExportSave {
+    /// Save path
+    save: PathBuf,
+    /// Save dump path
+    #[arg(short, long, value_name = "DUMP")]
+    output: PathBuf,
+    /// Save dump format (guessed from the extension by default, RON otherwise)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+}
+
+impl ExportSave {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let ExportSave {
+            save,
+            mut output,
+            format,
+        } = self;
+
+        let format = format.or_else(|| Format::from_path(&output)).unwrap_or_default();
+        output = Path::with_extension(&output, format.extension());
+
+        let save_file = fs::read(save).context("Failed to open the save file")?;
+        let save_game = SaveKind::deserialize_with(save_file, class_sizes).context("Failed to parse the save file")?;
+
+        let dump = format.serialize(&save_game)?;
+
+        super::backup(&output)?;
+        fs::write(output, dump).context("Failed to write the save dump")?;
+
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+pub struct ImportSave {
+    /// Save dump path (.ron, .json, .yaml)
+    input: PathBuf,
+    /// New save path (.xbsav for Xbox 360)
+    #[arg(short, long, value_name = "NEW_SAVE")]
+    output: PathBuf,
+    /// Save dump format (guessed from the extension by default)
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+}
+
+impl ImportSave {
+    pub fn execute(self) -> Result<()> {
+        let ImportSave { input, output, format } = self;
+
+        let format = format.or_else(|| Format::from_path(&input)).unwrap_or_default();
+
+        let dump = fs::read(input).context("Failed to open the save dump")?;
+        let save_game: SaveKind = format.deserialize(&dump).context("Failed to parse the save dump")?;
+
+        super::write_save(&save_game, &output)
+    }
+}
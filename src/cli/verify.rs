@@ -0,0 +1,128 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+
+use trilogy_save_editor::{
+    save_data::{
+        mass_effect_1::Me1SaveGame,
+        mass_effect_1_le::{legacy::ClassSizes, Me1LeMagicNumber, Me1LeSaveGame},
+    },
+    save_game::{Checksum, SaveKind},
+    unreal,
+};
+
+#[derive(Args)]
+pub struct Verify {
+    /// Save path
+    save: PathBuf,
+    /// Rewrite the checksum of the save, nothing else is changed, even if the save does not parse
+    #[arg(short, long, requires = "output")]
+    repair: bool,
+    /// Repaired save path
+    #[arg(short, long, requires = "repair", value_name = "NEW_SAVE")]
+    output: Option<PathBuf>,
+}
+
+impl Verify {
+    pub fn execute(self, class_sizes: &ClassSizes) -> Result<()> {
+        let Verify { save, output, .. } = self;
+
+        let mut save_file = fs::read(save).context("Failed to open the save file")?;
+
+        // Read without parsing the save, a save that does not parse can be checked and repaired
+        let checksum = Checksum::read(&save_file);
+
+        // `--repair` and `--output` require each other
+        if let Some(output) = output {
+            let checksum = checksum.context("This save has no checksum")?;
+            if checksum.is_valid() {
+                println!("Checksum: OK ({:#010x}), nothing to repair", checksum.stored);
+            } else {
+                checksum.repair(&mut save_file);
+                println!("Checksum: {:#010x} -> {:#010x}", checksum.stored, checksum.computed);
+            }
+
+            super::backup(&output)?;
+            fs::write(output, save_file).context("Failed to write the new save file")?;
+            return Ok(());
+        }
+
+        let mut is_valid = true;
+
+        match checksum {
+            Some(ref checksum) if checksum.is_valid() => println!("Checksum: OK ({:#010x})", checksum.stored),
+            Some(ref checksum) => {
+                is_valid = false;
+                println!(
+                    "Checksum: MISMATCH (stored {:#010x}, computed {:#010x})",
+                    checksum.stored, checksum.computed
+                );
+            }
+            None => println!("Checksum: none for this game"),
+        }
+
+        if unreal::Deserializer::from_bytes::<Me1LeMagicNumber>(&save_file).is_ok() {
+            match Me1LeSaveGame::chunk_issues(&save_file) {
+                Ok(issues) if issues.is_empty() => println!("Chunk headers: OK"),
+                Ok(issues) => {
+                    is_valid = false;
+                    println!("Chunk headers: {} issue(s)", issues.len());
+                    for issue in issues {
+                        println!("  {issue}");
+                    }
+                }
+                Err(err) => {
+                    is_valid = false;
+                    println!("Chunk headers: unreadable ({err})");
+                }
+            }
+        }
+
+        let save_game = match SaveKind::deserialize_with(save_file.clone(), class_sizes) {
+            Ok(save_game) => save_game,
+            Err(err) => {
+                println!("Parse: FAILED\n{err:#}");
+                bail!("The save did not pass verification");
+            }
+        };
+
+        let round_trip = save_game.serialize_for_platform(save_game.is_xbox360())?;
+
+        // The compression is not reproducible, compressed saves are compared once decompressed
+        let (label, original, round_trip) = match save_game {
+            SaveKind::MassEffect1(_) => (
+                "Round trip (unzipped)",
+                Me1SaveGame::unzip(&save_file)?,
+                Me1SaveGame::unzip(&round_trip)?,
+            ),
+            SaveKind::MassEffect1Le(_) => (
+                "Round trip (decompressed)",
+                Me1LeSaveGame::decompress(&save_file)?,
+                Me1LeSaveGame::decompress(&round_trip)?,
+            ),
+            _ => ("Round trip", save_file, round_trip),
+        };
+
+        let first_diff = original.iter().zip(&round_trip).position(|(a, b)| a != b).or_else(|| {
+            // One is a prefix of the other
+            (original.len() != round_trip.len()).then(|| original.len().min(round_trip.len()))
+        });
+        match first_diff {
+            None => println!("{label}: byte-identical"),
+            Some(offset) => {
+                is_valid = false;
+                println!(
+                    "{label}: differs from byte offset {offset} ({offset:#x}), {} bytes read, {} bytes written",
+                    original.len(),
+                    round_trip.len()
+                );
+            }
+        }
+
+        if !is_valid {
+            bail!("The save did not pass verification");
+        }
+        Ok(())
+    }
+}
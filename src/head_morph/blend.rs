@@ -0,0 +1,188 @@
+use anyhow::{bail, ensure, Result};
+
+use crate::{
+    save_data::shared::{
+        appearance::{HeadMorph, LinearColor},
+        Vector3D,
+    },
+    IndexMap,
+};
+
+trait Blend: Copy {
+    fn weighted(self, weight: f32) -> Self;
+    fn add(self, other: Self) -> Self;
+}
+
+impl Blend for f32 {
+    fn weighted(self, weight: f32) -> Self {
+        self * weight
+    }
+
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+impl Blend for Vector3D {
+    fn weighted(self, weight: f32) -> Self {
+        Vector3D {
+            x: self.x * weight,
+            y: self.y * weight,
+            z: self.z * weight,
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        Vector3D {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl Blend for LinearColor {
+    fn weighted(self, weight: f32) -> Self {
+        LinearColor {
+            r: self.r * weight,
+            g: self.g * weight,
+            b: self.b * weight,
+            a: self.a * weight,
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        LinearColor {
+            r: self.r + other.r,
+            g: self.g + other.g,
+            b: self.b + other.b,
+            a: self.a + other.a,
+        }
+    }
+}
+
+// `None` if all the weights are 0
+fn blend_values<T: Blend>(values: impl Iterator<Item = (T, f32)>) -> Option<T> {
+    let (sum, total) = values.fold((None, 0.0), |(sum, total), (value, weight)| {
+        let value = value.weighted(weight);
+        (Some(sum.map_or(value, |sum: T| sum.add(value))), total + weight)
+    });
+    (total > 0.0)
+        .then(|| sum.map(|sum| sum.weighted(1.0 / total)))
+        .flatten()
+}
+
+// A parameter missing from some head morphs is blended over the others, if they all have a weight
+// of 0 the value of the first one is kept
+fn blend_params<T: Blend>(maps: &[(&IndexMap<String, T>, f32)]) -> IndexMap<String, T> {
+    let mut blended = IndexMap::default();
+    for (map, _) in maps {
+        for (name, first) in map.iter() {
+            if blended.contains_key(name) {
+                continue;
+            }
+            let values = maps.iter().filter_map(|(map, weight)| Some((*map.get(name)?, *weight)));
+            blended.insert(name.clone(), blend_values(values).unwrap_or(*first));
+        }
+    }
+    blended
+}
+
+fn field<'a, T>(head_morphs: &[(&'a HeadMorph, f32)], field: fn(&'a HeadMorph) -> &'a T) -> Vec<(&'a T, f32)> {
+    head_morphs
+        .iter()
+        .map(|(head_morph, weight)| (field(head_morph), *weight))
+        .collect()
+}
+
+fn blend_vertices(lod: usize, vertices: &[(&Vec<Vector3D>, f32)]) -> Result<Vec<Vector3D>> {
+    let len = vertices[0].0.len();
+    if let Some((other, _)) = vertices.iter().find(|(other, _)| other.len() != len) {
+        bail!(
+            "Cannot blend lod{lod} of {len} vertices with lod{lod} of {} vertices",
+            other.len()
+        );
+    }
+
+    Ok((0..len)
+        .map(|idx| blend_values(vertices.iter().map(|(vertices, weight)| (vertices[idx], *weight))))
+        .collect::<Option<_>>()
+        .unwrap_or_else(|| vertices[0].0.clone()))
+}
+
+// Weighted average of the numeric fields (morph features, offset bones, vertices, scalar and vector
+// parameters), the meshes and the textures are those of `head_morphs[source]`
+pub fn blend(head_morphs: &[(&HeadMorph, f32)], source: usize) -> Result<HeadMorph> {
+    ensure!(!head_morphs.is_empty(), "Nothing to blend");
+    ensure!(
+        source < head_morphs.len(),
+        "Source {source} out of range, there are {} head morphs",
+        head_morphs.len()
+    );
+    ensure!(
+        head_morphs
+            .iter()
+            .all(|(_, weight)| weight.is_finite() && *weight >= 0.0),
+        "Weights must be positive numbers"
+    );
+    ensure!(
+        head_morphs.iter().any(|(_, weight)| *weight > 0.0),
+        "At least one weight must be greater than 0"
+    );
+
+    let source = head_morphs[source].0;
+    Ok(HeadMorph {
+        hair_mesh: source.hair_mesh.clone(),
+        accessory_mesh: source.accessory_mesh.clone(),
+        morph_features: blend_params(&field(head_morphs, |head_morph| &head_morph.morph_features)),
+        offset_bones: blend_params(&field(head_morphs, |head_morph| &head_morph.offset_bones)),
+        lod0_vertices: blend_vertices(0, &field(head_morphs, |head_morph| &head_morph.lod0_vertices))?,
+        lod1_vertices: blend_vertices(1, &field(head_morphs, |head_morph| &head_morph.lod1_vertices))?,
+        lod2_vertices: blend_vertices(2, &field(head_morphs, |head_morph| &head_morph.lod2_vertices))?,
+        lod3_vertices: blend_vertices(3, &field(head_morphs, |head_morph| &head_morph.lod3_vertices))?,
+        scalar_parameters: blend_params(&field(head_morphs, |head_morph| &head_morph.scalar_parameters)),
+        vector_parameters: blend_params(&field(head_morphs, |head_morph| &head_morph.vector_parameters)),
+        texture_parameters: source.texture_parameters.clone(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::head_morph::{gibbed::GibbedGame, gibbed_fixture};
+
+    fn approx_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn blend_head_morphs() -> Result<()> {
+        let a = gibbed_fixture(GibbedGame::Me2)?;
+        let mut b = gibbed_fixture(GibbedGame::Me2)?;
+        b.lod0_vertices[0].x += 4.0;
+        *b.morph_features.first_mut().unwrap().1 += 2.0;
+        b.scalar_parameters.insert("Test.Scalar".to_owned(), 1.0);
+        b.hair_mesh = "Test.Hair".to_owned();
+
+        let blended = blend(&[(&a, 3.0), (&b, 1.0)], 0)?;
+        assert!(approx_eq(blended.lod0_vertices[0].x, a.lod0_vertices[0].x + 1.0));
+        assert!(approx_eq(blended.lod0_vertices[1].y, a.lod0_vertices[1].y));
+        let (name, value) = blended.morph_features.first().unwrap();
+        assert!(approx_eq(*value, a.morph_features[name] + 0.5));
+        // Only in `b`
+        assert_eq!(blended.scalar_parameters["Test.Scalar"], 1.0);
+        assert_eq!(blended.hair_mesh, a.hair_mesh);
+        assert_eq!(blend(&[(&a, 3.0), (&b, 1.0)], 1)?.hair_mesh, "Test.Hair");
+
+        // A weight of 0 keeps the other head morph
+        let blended = blend(&[(&a, 0.0), (&b, 1.0)], 0)?;
+        assert!(blended.lod0_vertices == b.lod0_vertices);
+
+        assert!(blend(&[(&a, 0.0), (&b, 0.0)], 0).is_err());
+        assert!(blend(&[(&a, 1.0)], 1).is_err());
+        b.lod0_vertices.pop();
+        assert!(blend(&[(&a, 1.0), (&b, 1.0)], 0).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use std::{fmt, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{plot_db::PlotGame, save_data::shared::appearance::HeadMorph, IndexMap};
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+impl Gender {
+    pub fn from_is_female(is_female: bool) -> Self {
+        if is_female {
+            Gender::Female
+        } else {
+            Gender::Male
+        }
+    }
+}
+
+impl fmt::Display for Gender {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Gender::Male => f.write_str("male"),
+            Gender::Female => f.write_str("female"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HeadMorphDb {
+    heads: Vec<Head>,
+    #[serde(default)]
+    remaps: Vec<Remap>,
+}
+
+// What a head morph must match in a game, for a gender
+#[derive(Deserialize)]
+struct Head {
+    game: PlotGame,
+    gender: Gender,
+    // `None` for the LODs whose vertex count is not known
+    #[serde(default)]
+    lod_vertices: [Option<usize>; 4],
+    #[serde(default)]
+    meshes: Vec<String>,
+    #[serde(default)]
+    textures: Vec<String>,
+    // The meshes and textures are every asset of the game for this gender, only then are the
+    // other ones reported
+    #[serde(default)]
+    complete_assets: bool,
+}
+
+#[derive(Deserialize)]
+struct Remap {
+    from: PlotGame,
+    to: PlotGame,
+    names: IndexMap<String, String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    // The game will display a broken face
+    Error,
+    // Not known to exist in the game
+    Warning,
+}
+
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "Error: {}", self.message),
+            Severity::Warning => write!(f, "Warning: {}", self.message),
+        }
+    }
+}
+
+impl HeadMorphDb {
+    // Embedded database, or `<database_dir>/head_morph_db.ron` if it exists
+    pub fn load(database_dir: Option<&Path>) -> Result<Self> {
+        if let Some(path) = database_dir.map(|dir| dir.join("head_morph_db.ron")) {
+            if path.exists() {
+                let file = fs::read_to_string(&path).context("Failed to open the head morph database")?;
+                return Self::from_ron(&file).with_context(|| format!("Failed to parse {}", path.display()));
+            }
+        }
+        Self::from_ron(include_str!("../../databases/head_morph_db.ron"))
+    }
+
+    pub fn from_ron(input: &str) -> Result<Self> {
+        Ok(ron::from_str(input)?)
+    }
+
+    pub fn check(&self, head_morph: &HeadMorph, game: PlotGame, gender: Gender) -> Vec<Issue> {
+        let Some(head) = self
+            .heads
+            .iter()
+            .find(|head| head.game == game && head.gender == gender)
+        else {
+            return Vec::new();
+        };
+        let mut issues = Vec::new();
+
+        let lods = [
+            &head_morph.lod0_vertices,
+            &head_morph.lod1_vertices,
+            &head_morph.lod2_vertices,
+            &head_morph.lod3_vertices,
+        ];
+        for (lod, (vertices, expected)) in lods.into_iter().zip(head.lod_vertices).enumerate() {
+            let Some(expected) = expected.filter(|&expected| vertices.len() != expected) else {
+                continue;
+            };
+            // The games themselves save most heads with empty lod1 to lod3, only lod0 breaks the face
+            let severity = if lod == 0 { Severity::Error } else { Severity::Warning };
+            issues.push(Issue {
+                severity,
+                message: format!(
+                    "lod{lod}_vertices has {} vertices, {game} ({gender}) heads have {expected}",
+                    vertices.len()
+                ),
+            });
+        }
+
+        // An asset missing from an incomplete list may well exist in the game
+        if !head.complete_assets {
+            return issues;
+        }
+
+        let meshes = std::iter::once(&head_morph.hair_mesh).chain(&head_morph.accessory_mesh);
+        if !head.meshes.is_empty() {
+            for mesh in meshes.filter(|mesh| !head.meshes.contains(mesh)) {
+                issues.push(Issue {
+                    severity: Severity::Warning,
+                    message: format!("mesh `{mesh}` is not known for {game} ({gender})"),
+                });
+            }
+        }
+
+        if !head.textures.is_empty() {
+            let textures = head_morph
+                .texture_parameters
+                .iter()
+                .filter(|(_, texture)| *texture != "None" && !head.textures.contains(texture));
+            for (parameter, texture) in textures {
+                issues.push(Issue {
+                    severity: Severity::Warning,
+                    message: format!("texture `{texture}` ({parameter}) is not known for {game} ({gender})"),
+                });
+            }
+        }
+
+        issues
+    }
+
+    // Rename the meshes and textures known to have another name in `to`, returns the renames
+    pub fn convert(&self, head_morph: &mut HeadMorph, from: PlotGame, to: PlotGame) -> Vec<(String, String)> {
+        let mut renames = Vec::new();
+
+        let remaps = self.remaps.iter().filter(|remap| remap.from == from && remap.to == to);
+        for remap in remaps {
+            let names = std::iter::once(&mut head_morph.hair_mesh)
+                .chain(&mut head_morph.accessory_mesh)
+                .chain(head_morph.texture_parameters.values_mut());
+            for name in names {
+                if let Some(new_name) = remap.names.get(name) {
+                    renames.push((name.clone(), new_name.clone()));
+                    *name = new_name.clone();
+                }
+            }
+        }
+
+        renames
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::save_game::{SaveGame, SaveKind};
+
+    fn head_morph(path: &str) -> Result<HeadMorph> {
+        let save_game = SaveKind::deserialize(fs::read(path)?)?;
+        Ok(save_game.head_morph().unwrap().into_owned().unwrap())
+    }
+
+    #[test]
+    fn check() -> Result<()> {
+        let db = HeadMorphDb::load(None)?;
+        let me1 = head_morph("test/ME1LeSave.pcsav")?;
+        let me3 = head_morph("test/ME3Save.pcsav")?;
+
+        assert!(db.check(&me1, PlotGame::Me1, Gender::Female).is_empty());
+        // The original ME1 saves lod1 and lod2 too
+        assert!(db.check(&head_morph("test/ME1Save.MassEffectSave")?, PlotGame::Me1, Gender::Female).is_empty());
+        assert!(db.check(&me3, PlotGame::Me3, Gender::Female).is_empty());
+        // Nothing known about male heads
+        assert!(db.check(&me3, PlotGame::Me1, Gender::Male).is_empty());
+
+        let issues = db.check(&me1, PlotGame::Me3, Gender::Female);
+        let errors: Vec<_> = issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            ["Error: lod0_vertices has 2232 vertices, me3 (female) heads have 2390"]
+        );
+        // The bundled asset lists only come from a few saves
+        assert_eq!(issues.len(), errors.len());
+
+        // Only lod0 is an error
+        let db = HeadMorphDb::from_ron(
+            r#"(heads: [(game: me3, gender: female, lod_vertices: (Some(2390), Some(1000), None, None))])"#,
+        )?;
+        let issues: Vec<_> = db.check(&me3, PlotGame::Me3, Gender::Female).iter().map(ToString::to_string).collect();
+        assert_eq!(issues, ["Warning: lod1_vertices has 0 vertices, me3 (female) heads have 1000"]);
+
+        // Unknown assets are only reported against complete lists
+        let ron = |complete_assets| {
+            format!(
+                r#"(heads: [(game: me3, gender: female, meshes: ["Test.Hair"], complete_assets: {complete_assets})])"#
+            )
+        };
+        let db = HeadMorphDb::from_ron(&ron(false))?;
+        assert!(db.check(&me3, PlotGame::Me3, Gender::Female).is_empty());
+        let db = HeadMorphDb::from_ron(&ron(true))?;
+        let issues = db.check(&me3, PlotGame::Me3, Gender::Female);
+        assert!(!issues.is_empty() && issues.iter().all(|issue| issue.severity == Severity::Warning));
+
+        Ok(())
+    }
+
+    #[test]
+    fn convert() -> Result<()> {
+        let db = HeadMorphDb::from_ron(
+            r#"(
+                heads: [],
+                remaps: [
+                    (from: me2, to: me3, names: { "BIOG_HMF_HIR_PRO.Classy.HMF_HIR_Cls_MDL": "Test.Hair" }),
+                ],
+            )"#,
+        )?;
+        let mut me2 = head_morph("test/ME2Save.pcsav")?;
+
+        assert!(db.convert(&mut me2, PlotGame::Me3, PlotGame::Me2).is_empty());
+        let renames = db.convert(&mut me2, PlotGame::Me2, PlotGame::Me3);
+        assert_eq!(renames.len(), 1);
+        assert_eq!(me2.hair_mesh, "Test.Hair");
+
+        // Embedded remaps, as done by ME3 when importing an ME2 character
+        let db = HeadMorphDb::load(None)?;
+        let mut me2 = head_morph("test/ME2Save.pcsav")?;
+        let renames = db.convert(&mut me2, PlotGame::Me2, PlotGame::Me3);
+        assert_eq!(renames.len(), 1);
+        let me3 = head_morph("test/ME3Save.pcsav")?;
+        assert_eq!(me2.texture_parameters["HED_Lash_Diff"], me3.texture_parameters["HED_Lash_Diff"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    save_data::shared::{
+        appearance::{HeadMorph, LinearColor},
+        Vector3D,
+    },
+    IndexMap,
+};
+
+// `old` is `None` for an addition, `new` is `None` for a removal
+pub struct ParamChange<T> {
+    pub name: String,
+    pub old: Option<T>,
+    pub new: Option<T>,
+}
+
+pub struct LodStats {
+    pub lod: usize,
+    pub old_len: usize,
+    pub new_len: usize,
+    // Only the vertices present in both head morphs are compared
+    pub moved: usize,
+    pub max: f32,
+    pub mean: f32,
+    // Index and displacement, the most moved first
+    pub most_moved: Vec<(usize, f32)>,
+}
+
+pub struct HeadMorphDiff {
+    pub hair_mesh: Option<(String, String)>,
+    pub added_accessories: Vec<String>,
+    pub removed_accessories: Vec<String>,
+    pub morph_features: Vec<ParamChange<f32>>,
+    pub offset_bones: Vec<ParamChange<Vector3D>>,
+    pub scalar_parameters: Vec<ParamChange<f32>>,
+    pub vector_parameters: Vec<ParamChange<LinearColor>>,
+    pub texture_parameters: Vec<ParamChange<String>>,
+    pub lods: [LodStats; 4],
+}
+
+fn diff_params<T: Clone + PartialEq>(old: &IndexMap<String, T>, new: &IndexMap<String, T>) -> Vec<ParamChange<T>> {
+    let names = old.keys().chain(new.keys().filter(|name| !old.contains_key(*name)));
+    names
+        .filter_map(|name| {
+            let (old, new) = (old.get(name), new.get(name));
+            (old != new).then(|| ParamChange {
+                name: name.to_owned(),
+                old: old.cloned(),
+                new: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+pub fn displacement(old: &Vector3D, new: &Vector3D) -> f32 {
+    ((new.x - old.x).powi(2) + (new.y - old.y).powi(2) + (new.z - old.z).powi(2)).sqrt()
+}
+
+fn lod_stats(lod: usize, old: &[Vector3D], new: &[Vector3D], top: usize) -> LodStats {
+    let mut displacements: Vec<_> = old
+        .iter()
+        .zip(new)
+        .map(|(old, new)| displacement(old, new))
+        .enumerate()
+        .collect();
+
+    let moved = displacements
+        .iter()
+        .filter(|(_, displacement)| *displacement > 0.0)
+        .count();
+    let max = displacements
+        .iter()
+        .map(|(_, displacement)| *displacement)
+        .fold(0.0, f32::max);
+    let mean = match displacements.len() {
+        0 => 0.0,
+        len => displacements.iter().map(|(_, displacement)| *displacement).sum::<f32>() / len as f32,
+    };
+
+    displacements.retain(|(_, displacement)| *displacement > 0.0);
+    displacements.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    displacements.truncate(top);
+
+    LodStats {
+        lod,
+        old_len: old.len(),
+        new_len: new.len(),
+        moved,
+        max,
+        mean,
+        most_moved: displacements,
+    }
+}
+
+impl HeadMorphDiff {
+    // `top` is the number of most moved vertices kept per LOD
+    pub fn new(old: &HeadMorph, new: &HeadMorph, top: usize) -> Self {
+        let accessories = |from: &HeadMorph, to: &HeadMorph| {
+            (to.accessory_mesh.iter())
+                .filter(|mesh| !from.accessory_mesh.contains(mesh))
+                .cloned()
+                .collect()
+        };
+
+        HeadMorphDiff {
+            hair_mesh: (old.hair_mesh != new.hair_mesh).then(|| (old.hair_mesh.clone(), new.hair_mesh.clone())),
+            added_accessories: accessories(old, new),
+            removed_accessories: accessories(new, old),
+            morph_features: diff_params(&old.morph_features, &new.morph_features),
+            offset_bones: diff_params(&old.offset_bones, &new.offset_bones),
+            scalar_parameters: diff_params(&old.scalar_parameters, &new.scalar_parameters),
+            vector_parameters: diff_params(&old.vector_parameters, &new.vector_parameters),
+            texture_parameters: diff_params(&old.texture_parameters, &new.texture_parameters),
+            lods: [
+                lod_stats(0, &old.lod0_vertices, &new.lod0_vertices, top),
+                lod_stats(1, &old.lod1_vertices, &new.lod1_vertices, top),
+                lod_stats(2, &old.lod2_vertices, &new.lod2_vertices, top),
+                lod_stats(3, &old.lod3_vertices, &new.lod3_vertices, top),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::head_morph::{gibbed::GibbedGame, gibbed_fixture};
+
+    #[test]
+    fn head_morph_diff() -> Result<()> {
+        let old = gibbed_fixture(GibbedGame::Me2)?;
+        let mut new = gibbed_fixture(GibbedGame::Me2)?;
+
+        let diff = HeadMorphDiff::new(&old, &new, 5);
+        assert!(diff.morph_features.is_empty() && diff.hair_mesh.is_none());
+        assert!(diff.lods.iter().all(|lod| lod.moved == 0 && lod.max == 0.0));
+
+        new.lod0_vertices[10].x += 3.0;
+        new.lod0_vertices[20].y += 4.0;
+        new.lod0_vertices[20].z += 3.0;
+        let (name, value) = new.morph_features.first_mut().unwrap();
+        let name = name.to_owned();
+        *value += 1.0;
+        new.accessory_mesh.push("Test.Accessory".to_owned());
+
+        let diff = HeadMorphDiff::new(&old, &new, 5);
+        let lod0 = &diff.lods[0];
+        assert_eq!(lod0.moved, 2);
+        assert_eq!(lod0.max, 5.0);
+        assert_eq!(lod0.mean, 8.0 / old.lod0_vertices.len() as f32);
+        assert_eq!(lod0.most_moved, [(20, 5.0), (10, 3.0)]);
+        assert_eq!(diff.lods[1].moved, 0);
+
+        assert_eq!(diff.morph_features.len(), 1);
+        assert_eq!(diff.morph_features[0].name, name);
+        assert_eq!(diff.added_accessories, ["Test.Accessory"]);
+        assert!(diff.removed_accessories.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{bail, Result};
+
+use crate::{plot_db::PlotGame, save_data::shared::appearance::HeadMorph, unreal};
+
+// Head morph files of Gibbed's ME2 and ME3 save editors (.me2headmorph, .me3headmorph) :
+// a 31-byte header, followed by the head morph as it is in a PC save
+const HEADER_LEN: usize = 31;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GibbedGame {
+    Me2,
+    Me3,
+}
+
+impl GibbedGame {
+    fn magic(self) -> &'static [u8] {
+        match self {
+            GibbedGame::Me2 => b"GIBBEDMASSEFFECT2HEADMORPH",
+            GibbedGame::Me3 => b"GIBBEDMASSEFFECT3HEADMORPH",
+        }
+    }
+
+    // Save version of the game
+    fn version(self) -> u32 {
+        match self {
+            GibbedGame::Me2 => 29,
+            GibbedGame::Me3 => 59,
+        }
+    }
+
+    // The game whose saves the head morph can be imported into
+    pub fn game(self) -> PlotGame {
+        match self {
+            GibbedGame::Me2 => PlotGame::Me2,
+            GibbedGame::Me3 => PlotGame::Me3,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            GibbedGame::Me2 => "me2headmorph",
+            GibbedGame::Me3 => "me3headmorph",
+        }
+    }
+
+    // The game of a Gibbed head morph file
+    pub fn detect(file: &[u8]) -> Option<Self> {
+        [GibbedGame::Me2, GibbedGame::Me3]
+            .into_iter()
+            .find(|game| file.starts_with(game.magic()))
+    }
+}
+
+pub fn deserialize(file: &[u8]) -> Result<HeadMorph> {
+    if GibbedGame::detect(file).is_none() || file.len() < HEADER_LEN {
+        bail!("Not a Gibbed head morph");
+    }
+    Ok(unreal::Deserializer::from_bytes(&file[HEADER_LEN..])?)
+}
+
+pub fn serialize(head_morph: &HeadMorph, game: GibbedGame) -> Result<Vec<u8>> {
+    // Magic, a null byte and the save version
+    let mut output = Vec::from(game.magic());
+    output.push(0);
+    output.extend(game.version().to_le_bytes());
+    debug_assert_eq!(output.len(), HEADER_LEN);
+
+    output.extend(unreal::Serializer::to_vec(head_morph)?);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        for (path, game) in [
+            ("test/GibbedME2.me2headmorph", GibbedGame::Me2),
+            ("test/GibbedME3.me3headmorph", GibbedGame::Me3),
+        ] {
+            let input = fs::read(path)?;
+            assert_eq!(GibbedGame::detect(&input), Some(game));
+
+            let head_morph = deserialize(&input)?;
+            assert!(serialize(&head_morph, game)? == input, "{path}");
+        }
+
+        assert!(deserialize(b"(hair_mesh: \"\")").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt::Write;
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::save_data::shared::{appearance::HeadMorph, Vector3D};
+
+// The vertices of a head morph are in Unreal space (Z up, left-handed), OBJ and glTF are Y up and
+// right-handed : Y and Z are swapped, which is its own inverse and keeps the positions exact
+fn to_y_up(Vector3D { x, y, z }: Vector3D) -> [f32; 3] {
+    [x, z, y]
+}
+
+fn from_y_up([x, y, z]: [f32; 3]) -> Vector3D {
+    Vector3D { x, y: z, z: y }
+}
+
+// Head morphs only store positions, the triangles come from the base head of the game which
+// is not bundled, `faces` is empty when no topology is available (point cloud)
+pub struct Mesh {
+    pub vertices: Vec<Vector3D>,
+    pub faces: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vector3D>, faces: Vec<[u32; 3]>) -> Result<Self> {
+        if let Some(idx) = faces.iter().flatten().find(|idx| **idx as usize >= vertices.len()) {
+            bail!(
+                "Face index {} out of range, the mesh has {} vertices",
+                idx + 1,
+                vertices.len()
+            );
+        }
+        Ok(Mesh { vertices, faces })
+    }
+
+    // Positions and triangles of a Wavefront OBJ, polygons are triangulated as fans,
+    // normals and texture coordinates are ignored
+    pub fn from_obj(input: &str) -> Result<Self> {
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+
+        for (line_idx, line) in input.lines().enumerate() {
+            let line_number = line_idx + 1;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let mut position = [0.0; 3];
+                    for coord in &mut position {
+                        let token = tokens
+                            .next()
+                            .with_context(|| format!("Line {line_number}: incomplete vertex"))?;
+                        *coord = token
+                            .parse()
+                            .with_context(|| format!("Line {line_number}: invalid coordinate `{token}`"))?;
+                    }
+                    vertices.push(from_y_up(position));
+                }
+                Some("f") => {
+                    let indices = tokens
+                        .map(|token| {
+                            let index = token.split('/').next().unwrap_or_default();
+                            let index: i64 = index
+                                .parse()
+                                .with_context(|| format!("Line {line_number}: invalid face index `{token}`"))?;
+                            // Negative indices are relative to the last vertex
+                            let index = if index < 0 {
+                                vertices.len() as i64 + index
+                            } else {
+                                index - 1
+                            };
+                            u32::try_from(index)
+                                .ok()
+                                .with_context(|| format!("Line {line_number}: face index `{token}` out of range"))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    ensure!(
+                        indices.len() >= 3,
+                        "Line {line_number}: a face needs at least 3 vertices"
+                    );
+                    for pair in indices[1..].windows(2) {
+                        faces.push([indices[0], pair[0], pair[1]]);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Mesh::new(vertices, faces)
+    }
+
+    pub fn to_obj(&self) -> String {
+        let mut output = String::from("# Exported by Trilogy Save Editor\n");
+        for vertex in &self.vertices {
+            let [x, y, z] = to_y_up(*vertex);
+            let _ = writeln!(output, "v {x} {y} {z}");
+        }
+        for [a, b, c] in &self.faces {
+            let _ = writeln!(output, "f {} {} {}", a + 1, b + 1, c + 1);
+        }
+        output
+    }
+
+    // Binary glTF (.glb), a single file with the JSON and the buffer
+    pub fn to_glb(&self) -> Result<Vec<u8>> {
+        ensure!(!self.vertices.is_empty(), "Cannot export a mesh without vertices");
+
+        let positions: Vec<_> = self.vertices.iter().map(|vertex| to_y_up(*vertex)).collect();
+        let mut min = positions[0];
+        let mut max = positions[0];
+        for position in &positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+
+        let mut buffer: Vec<u8> = positions
+            .iter()
+            .flatten()
+            .flat_map(|coord| coord.to_le_bytes())
+            .collect();
+        let positions_len = buffer.len();
+        buffer.extend(self.faces.iter().flatten().flat_map(|idx| idx.to_le_bytes()));
+        let indices_len = buffer.len() - positions_len;
+
+        let mut buffer_views = vec![json!({ "buffer": 0, "byteOffset": 0, "byteLength": positions_len })];
+        let mut accessors = vec![json!({
+            "bufferView": 0,
+            "componentType": 5126, // FLOAT
+            "count": positions.len(),
+            "type": "VEC3",
+            "min": min,
+            "max": max,
+        })];
+        let mut primitive = json!({ "attributes": { "POSITION": 0 }, "mode": 0 }); // POINTS
+        if !self.faces.is_empty() {
+            buffer_views.push(json!({ "buffer": 0, "byteOffset": positions_len, "byteLength": indices_len }));
+            accessors.push(json!({
+                "bufferView": 1,
+                "componentType": 5125, // UNSIGNED_INT
+                "count": self.faces.len() * 3,
+                "type": "SCALAR",
+            }));
+            primitive = json!({ "attributes": { "POSITION": 0 }, "indices": 1, "mode": 4 });
+            // TRIANGLES
+        }
+
+        let gltf = json!({
+            "asset": { "version": "2.0", "generator": "Trilogy Save Editor" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0, "name": "Head" }],
+            "meshes": [{ "primitives": [primitive] }],
+            "buffers": [{ "byteLength": buffer.len() }],
+            "bufferViews": buffer_views,
+            "accessors": accessors,
+        });
+
+        // Chunks are 4-byte aligned, with spaces for the JSON and zeros for the buffer
+        let mut json = serde_json::to_vec(&gltf)?;
+        json.resize((json.len() + 3) & !3, b' ');
+        buffer.resize((buffer.len() + 3) & !3, 0);
+
+        let total_len = 12 + 8 + json.len() + 8 + buffer.len();
+        let mut output = Vec::with_capacity(total_len);
+        output.extend(b"glTF");
+        output.extend(2u32.to_le_bytes());
+        output.extend((total_len as u32).to_le_bytes());
+        output.extend((json.len() as u32).to_le_bytes());
+        output.extend(b"JSON");
+        output.extend(json);
+        output.extend((buffer.len() as u32).to_le_bytes());
+        output.extend(b"BIN\0");
+        output.extend(buffer);
+        Ok(output)
+    }
+}
+
+// Decimation of lod0 into the other LODs : each vertex of lodN is the lod0 vertex at the given index,
+// e.g. `(lod1: [0, 2, 5, ...], lod2: [...], lod3: [...])`, a missing or empty LOD is left untouched.
+// No map is bundled, the correspondence between the LODs of the game heads is not known
+#[derive(Deserialize)]
+pub struct LodMap {
+    #[serde(default)]
+    lod1: Vec<usize>,
+    #[serde(default)]
+    lod2: Vec<usize>,
+    #[serde(default)]
+    lod3: Vec<usize>,
+}
+
+impl LodMap {
+    pub fn from_ron(input: &str) -> Result<Self> {
+        Ok(ron::from_str(input)?)
+    }
+
+    // Regenerate lod1 to lod3 from lod0, returns the regenerated LODs
+    pub fn apply(&self, head_morph: &mut HeadMorph) -> Result<Vec<usize>> {
+        let lod0 = &head_morph.lod0_vertices;
+        let lods = [
+            (1, &self.lod1, &mut head_morph.lod1_vertices),
+            (2, &self.lod2, &mut head_morph.lod2_vertices),
+            (3, &self.lod3, &mut head_morph.lod3_vertices),
+        ];
+
+        let mut regenerated = Vec::new();
+        for (lod, indices, vertices) in lods {
+            if indices.is_empty() {
+                continue;
+            }
+            *vertices = indices
+                .iter()
+                .map(|idx| {
+                    lod0.get(*idx).copied().with_context(|| {
+                        format!("lod{lod} maps to lod0 vertex {idx}, lod0 has {} vertices", lod0.len())
+                    })
+                })
+                .collect::<Result<_>>()?;
+            regenerated.push(lod);
+        }
+        Ok(regenerated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::head_morph::{gibbed::GibbedGame, gibbed_fixture};
+
+    fn vertices() -> Result<Vec<Vector3D>> {
+        Ok(gibbed_fixture(GibbedGame::Me3)?.lod0_vertices)
+    }
+
+    #[test]
+    fn obj() -> Result<()> {
+        let mesh = Mesh::new(vertices()?, vec![[0, 1, 2], [2, 1, 3]])?;
+        let output = Mesh::from_obj(&mesh.to_obj())?;
+        assert!(output.vertices == mesh.vertices);
+        assert_eq!(output.faces, mesh.faces);
+
+        let quad = Mesh::from_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1 -1//1\n")?;
+        assert_eq!(quad.faces, [[0, 1, 2], [0, 2, 3]]);
+        assert!(quad.vertices[2] == Vector3D { x: 1.0, y: 0.0, z: 1.0 });
+
+        assert!(Mesh::from_obj("v 0 0 0\nf 1 2 3\n").is_err());
+        assert!(Mesh::from_obj("v 0 0\n").is_err());
+        assert!(Mesh::from_obj("v 0 0 0\nf 1 1 4294967297\n").is_err());
+        assert!(Mesh::from_obj("v 0 0 0\nf 1 1 -2\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn glb() -> Result<()> {
+        let vertices = vertices()?;
+        for faces in [vec![], vec![[0, 1, 2]]] {
+            let glb = Mesh::new(vertices.clone(), faces.clone())?.to_glb()?;
+            assert_eq!(&glb[..4], b"glTF");
+            assert_eq!(u32::from_le_bytes(glb[8..12].try_into()?) as usize, glb.len());
+
+            let json_len = u32::from_le_bytes(glb[12..16].try_into()?) as usize;
+            assert_eq!(json_len % 4, 0);
+            let gltf: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_len])?;
+            assert_eq!(gltf["accessors"][0]["count"], vertices.len());
+            assert_eq!(
+                gltf["meshes"][0]["primitives"][0]["mode"],
+                if faces.is_empty() { 0 } else { 4 }
+            );
+
+            let bin = &glb[20 + json_len..];
+            assert_eq!(&bin[4..8], b"BIN\0");
+            assert_eq!(u32::from_le_bytes(bin[..4].try_into()?) as usize, bin.len() - 8);
+            assert!(bin.len() - 8 >= vertices.len() * 12 + faces.len() * 12);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn lod_map() -> Result<()> {
+        let mut head_morph = gibbed_fixture(GibbedGame::Me3)?;
+        let map = LodMap::from_ron("(lod1: [0, 2, 4], lod3: [])")?;
+        assert_eq!(map.apply(&mut head_morph)?, [1]);
+        assert!(head_morph.lod1_vertices[1] == head_morph.lod0_vertices[2]);
+        assert_eq!(head_morph.lod1_vertices.len(), 3);
+        assert!(head_morph.lod2_vertices.is_empty() && head_morph.lod3_vertices.is_empty());
+
+        let map = LodMap::from_ron("(lod2: [100000])")?;
+        assert!(map.apply(&mut head_morph).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+//! Tools working on a [`HeadMorph`](crate::save_data::shared::appearance::HeadMorph) alone.
+
+pub mod blend;
+pub mod compat;
+pub mod diff;
+pub mod gibbed;
+pub mod mesh;
+
+// The head morphs exported by Gibbed's editors, shared by the tests of the tools
+#[cfg(test)]
+fn gibbed_fixture(game: gibbed::GibbedGame) -> anyhow::Result<crate::save_data::shared::appearance::HeadMorph> {
+    let path = match game {
+        gibbed::GibbedGame::Me2 => "test/GibbedME2.me2headmorph",
+        gibbed::GibbedGame::Me3 => "test/GibbedME3.me3headmorph",
+    };
+    gibbed::deserialize(&std::fs::read(path)?)
+}
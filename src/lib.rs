@@ -0,0 +1,22 @@
+//! Mass Effect trilogy save editing: load a save with [`save_game::SaveKind::deserialize`], edit it
+//! (head morph, plot variables, the whole [`save_data`] model) and write it back with
+//! [`save_game::SaveKind::serialize`].
+
+#![warn(clippy::all)]
+
+#[macro_use]
+extern crate derive_more;
+
+pub mod head_morph;
+pub mod plot_db;
+pub mod plot_patch;
+pub mod reflect;
+pub mod save_data;
+pub mod save_diff;
+pub mod save_game;
+pub mod save_merge;
+pub mod unreal;
+
+use indexmap::IndexMap as RealIndexMap;
+
+pub type IndexMap<K, V> = RealIndexMap<K, V, ahash::RandomState>;
@@ -1,26 +1,22 @@
 #![warn(clippy::all)]
 
-#[macro_use]
-extern crate derive_more;
-
 mod cli;
-mod save_data;
-mod save_game;
-mod unreal;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use indexmap::IndexMap as RealIndexMap;
-
-use crate::cli::{ExportHeadMorph, ImportHeadMorph};
 
-pub type IndexMap<K, V> = RealIndexMap<K, V, ahash::RandomState>;
+use crate::cli::{
+    Convert, Diff, Edit, ExportHeadMesh, ExportHeadMorph, ExportSave, GetField, HeadMorphTools, ImportHeadMesh,
+    ImportHeadMorph, ImportSave, Info, LoadOptions, Merge, Plot, SetField, Verify,
+};
 
 #[derive(Parser)]
 #[command(author, version)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[command(flatten)]
+    load: LoadOptions,
 }
 
 #[derive(Subcommand)]
@@ -29,16 +25,63 @@ enum Commands {
     ImportHeadMorph(ImportHeadMorph),
     /// Head Morph export
     ExportHeadMorph(ExportHeadMorph),
+    /// Head Morph vertices export to a mesh (OBJ, glTF)
+    ///
+    /// Head morphs only store vertex positions and the faces of the game heads are not bundled, the
+    /// mesh is a point cloud unless the faces are taken from a base head with `--faces`.
+    ExportHeadMesh(ExportHeadMesh),
+    /// Head Morph vertices import from an edited OBJ mesh
+    ImportHeadMesh(ImportHeadMesh),
+    /// Head Morph tools
+    HeadMorph(HeadMorphTools),
+    /// Full save export
+    ExportSave(ExportSave),
+    /// Full save import
+    ImportSave(ImportSave),
+    /// Print a summary of the save
+    Info(Info),
+    /// Convert a save between PC and Xbox 360
+    Convert(Convert),
+    /// Plot variables
+    Plot(Plot),
+    /// Print any field of the save, e.g. `player.credits`
+    Get(GetField),
+    /// Change any field of the save, e.g. `squad[2].talent_points`
+    Set(SetField),
+    /// Interactive editing session, the save is loaded once and written on demand
+    Edit(Edit),
+    /// Compare two saves of the same game, plot variables and every other field
+    Diff(Diff),
+    /// Three-way merge of the plot, the journal and the codex, or fields taken from other saves
+    Merge(Merge),
+    /// Check the checksum and the integrity of a save, or repair its checksum
+    Verify(Verify),
     /// Print license (CECILL-2.1)
     License,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let class_sizes = cli.load.class_sizes();
+    let class_sizes = &class_sizes;
 
     match cli.command {
-        Commands::ImportHeadMorph(import) => import.execute(),
-        Commands::ExportHeadMorph(export) => export.execute(),
+        Commands::ImportHeadMorph(import) => import.execute(class_sizes),
+        Commands::ExportHeadMorph(export) => export.execute(class_sizes),
+        Commands::ExportHeadMesh(export) => export.execute(class_sizes),
+        Commands::ImportHeadMesh(import) => import.execute(class_sizes),
+        Commands::HeadMorph(head_morph) => head_morph.execute(class_sizes),
+        Commands::ExportSave(export) => export.execute(class_sizes),
+        Commands::ImportSave(import) => import.execute(),
+        Commands::Info(info) => info.execute(class_sizes),
+        Commands::Convert(convert) => convert.execute(class_sizes),
+        Commands::Plot(plot) => plot.execute(class_sizes),
+        Commands::Get(get) => get.execute(class_sizes),
+        Commands::Set(set) => set.execute(class_sizes),
+        Commands::Edit(edit) => edit.execute(class_sizes),
+        Commands::Diff(diff) => diff.execute(class_sizes),
+        Commands::Merge(merge) => merge.execute(class_sizes),
+        Commands::Verify(verify) => verify.execute(class_sizes),
         Commands::License => {
             println!(include_str!("../LICENSE.txt"));
             Ok(())
@@ -0,0 +1,268 @@
+use std::{collections::HashSet, fmt, fs, path::Path, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::IndexMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlotGame {
+    Me1,
+    Me2,
+    Me3,
+}
+
+impl PlotGame {
+    fn file_name(self) -> &'static str {
+        match self {
+            PlotGame::Me1 => "me1_plot_db.ron",
+            PlotGame::Me2 => "me2_plot_db.ron",
+            PlotGame::Me3 => "me3_plot_db.ron",
+        }
+    }
+}
+
+impl FromStr for PlotGame {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let game = match s.to_ascii_lowercase().as_str() {
+            "me1" => PlotGame::Me1,
+            "me2" => PlotGame::Me2,
+            "me3" => PlotGame::Me3,
+            _ => bail!("Unknown game `{s}`, expected me1, me2 or me3"),
+        };
+        Ok(game)
+    }
+}
+
+impl fmt::Display for PlotGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let game = match self {
+            PlotGame::Me1 => "me1",
+            PlotGame::Me2 => "me2",
+            PlotGame::Me3 => "me3",
+        };
+        f.pad(game)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PlotKind {
+    #[serde(rename = "bool", alias = "boolean")]
+    Boolean,
+    #[serde(rename = "int", alias = "integer")]
+    Integer,
+    #[serde(rename = "float")]
+    Float,
+}
+
+impl FromStr for PlotKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let kind = match s.to_ascii_lowercase().as_str() {
+            "bool" | "boolean" => PlotKind::Boolean,
+            "int" | "integer" => PlotKind::Integer,
+            "float" => PlotKind::Float,
+            _ => bail!("Unknown plot kind `{s}`, expected bool, int or float"),
+        };
+        Ok(kind)
+    }
+}
+
+impl fmt::Display for PlotKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            PlotKind::Boolean => "bool",
+            PlotKind::Integer => "int",
+            PlotKind::Float => "float",
+        };
+        f.pad(kind)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PlotDb {
+    categories: Vec<PlotCategory>,
+}
+
+#[derive(Deserialize)]
+pub struct PlotCategory {
+    pub name: String,
+    #[serde(default)]
+    pub booleans: IndexMap<usize, String>,
+    #[serde(default)]
+    pub integers: IndexMap<usize, String>,
+    #[serde(default)]
+    pub floats: IndexMap<usize, String>,
+}
+
+pub struct PlotVariable<'a> {
+    pub category: &'a str,
+    pub kind: PlotKind,
+    pub id: usize,
+    pub name: &'a str,
+}
+
+impl PlotDb {
+    // Embedded database, or `<database_dir>/meX_plot_db.ron` if it exists
+    pub fn load(game: PlotGame, database_dir: Option<&Path>) -> Result<Self> {
+        if let Some(path) = database_dir.map(|dir| dir.join(game.file_name())) {
+            if path.exists() {
+                let file = fs::read_to_string(&path).context("Failed to open the plot database")?;
+                return Self::from_ron(&file).with_context(|| format!("Failed to parse {}", path.display()));
+            }
+        }
+
+        let file = match game {
+            PlotGame::Me1 => include_str!("../databases/me1_plot_db.ron"),
+            PlotGame::Me2 => include_str!("../databases/me2_plot_db.ron"),
+            PlotGame::Me3 => include_str!("../databases/me3_plot_db.ron"),
+        };
+        Self::from_ron(file)
+    }
+
+    // An id or a name listed twice would make the lookups depend on the order of the categories
+    pub fn from_ron(input: &str) -> Result<Self> {
+        let plot_db: Self = ron::from_str(input)?;
+
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+        for variable in plot_db.variables() {
+            if !ids.insert((variable.kind, variable.id)) {
+                bail!("{} {} is listed more than once", variable.kind, variable.id);
+            }
+            if !names.insert(variable.name.to_lowercase()) {
+                bail!("`{}` is listed more than once", variable.name);
+            }
+        }
+        Ok(plot_db)
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = PlotVariable<'_>> {
+        fn variables<'a>(
+            category: &'a str,
+            kind: PlotKind,
+            variables: &'a IndexMap<usize, String>,
+        ) -> impl Iterator<Item = PlotVariable<'a>> {
+            variables.iter().map(move |(&id, name)| PlotVariable {
+                category,
+                kind,
+                id,
+                name,
+            })
+        }
+
+        self.categories.iter().flat_map(|category| {
+            variables(&category.name, PlotKind::Boolean, &category.booleans)
+                .chain(variables(&category.name, PlotKind::Integer, &category.integers))
+                .chain(variables(&category.name, PlotKind::Float, &category.floats))
+        })
+    }
+
+    // Case insensitive search in the variable names and categories
+    pub fn search<'a>(&'a self, pattern: &str) -> impl Iterator<Item = PlotVariable<'a>> {
+        let pattern = pattern.to_lowercase();
+        self.variables().filter(move |variable| {
+            variable.name.to_lowercase().contains(&pattern) || variable.category.to_lowercase().contains(&pattern)
+        })
+    }
+
+    pub fn find(&self, kind: PlotKind, id: usize) -> Option<PlotVariable<'_>> {
+        self.variables()
+            .find(|variable| variable.kind == kind && variable.id == id)
+    }
+
+    // Case insensitive, with the same case folding as the duplicate check of `from_ron`
+    pub fn find_by_name(&self, name: &str) -> Option<PlotVariable<'_>> {
+        let name = name.to_lowercase();
+        self.variables()
+            .find(|variable| variable.name.to_lowercase() == name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn embedded_databases() -> Result<()> {
+        for game in [PlotGame::Me1, PlotGame::Me2, PlotGame::Me3] {
+            let plot_db = PlotDb::load(game, None)?;
+            assert!(plot_db.variables().next().is_some(), "{game}");
+        }
+
+        let plot_db = PlotDb::load(PlotGame::Me2, None)?;
+        let variable = plot_db.find_by_name("PARAGON").unwrap();
+        assert!(variable.kind == PlotKind::Integer && variable.id == 2);
+        assert_eq!(variable.category, "Morality");
+        Ok(())
+    }
+
+    #[test]
+    fn duplicates() {
+        let duplicate_id = r#"(
+            categories: [
+                (name: "Citadel", booleans: { 10: "Met the council" }),
+                (name: "Feros", booleans: { 10: "Colony saved" }, integers: { 11: "Zhu's hope" }),
+            ],
+        )"#;
+        assert!(PlotDb::from_ron(duplicate_id).is_err());
+
+        // Same id, different kinds
+        assert!(PlotDb::from_ron(&duplicate_id.replace("booleans: { 10: \"Colony", "floats: { 10: \"Colony")).is_ok());
+
+        let duplicate_name = r#"(
+            categories: [
+                (name: "Citadel", booleans: { 10: "Spectre" }),
+                (name: "Feros", integers: { 3: "SPECTRE" }),
+            ],
+        )"#;
+        assert!(PlotDb::from_ron(duplicate_name).is_err());
+    }
+
+    #[test]
+    fn search() -> Result<()> {
+        let plot_db = PlotDb::from_ron(
+            r#"(
+                categories: [
+                    (
+                        name: "Citadel",
+                        booleans: { 10: "Met the council", 12: "Spectre" },
+                        integers: { 3: "Council reputation" },
+                    ),
+                    (
+                        name: "Feros",
+                        floats: { 7: "Colony saved" },
+                    ),
+                ],
+            )"#,
+        )?;
+
+        assert_eq!(plot_db.variables().count(), 4);
+        assert_eq!(plot_db.search("COUNCIL").count(), 2);
+        assert_eq!(
+            plot_db.search("feros").map(|variable| variable.id).collect::<Vec<_>>(),
+            [7]
+        );
+
+        let variable = plot_db.find(PlotKind::Integer, 3).unwrap();
+        assert_eq!(variable.name, "Council reputation");
+        assert_eq!(variable.category, "Citadel");
+        assert!(plot_db.find(PlotKind::Boolean, 3).is_none());
+
+        let variable = plot_db.find_by_name("spectre").unwrap();
+        assert!(variable.kind == PlotKind::Boolean && variable.id == 12);
+
+        // Not only ASCII
+        let plot_db = PlotDb::from_ron(r#"(categories: [(name: "Citadel", booleans: { 1: "Ézio" })])"#)?;
+        assert!(plot_db.find_by_name("ÉZIO").is_some());
+        assert!(PlotDb::from_ron(r#"(categories: [(name: "Citadel", booleans: { 1: "Ézio", 2: "éZIO" })])"#).is_err());
+
+        Ok(())
+    }
+}
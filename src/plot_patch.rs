@@ -0,0 +1,317 @@
+use std::fmt;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    plot_db::{PlotGame, PlotKind},
+    save_data::shared::plot::{check_plot_id, PlotAccess},
+};
+
+// A list of plot changes applied all at once
+#[derive(Deserialize)]
+pub struct PlotPatch {
+    pub changes: Vec<PlotChange>,
+}
+
+#[derive(Deserialize)]
+pub struct PlotChange {
+    pub table: PlotGame,
+    pub kind: PlotKind,
+    pub id: usize,
+    pub value: PlotValue,
+    // The change is only applied if the variable currently has this value
+    #[serde(default)]
+    pub expected: Option<PlotValue>,
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PlotValue {
+    Boolean(bool),
+    Integer(i32),
+    Float(f32),
+}
+
+impl PlotValue {
+    pub fn get(plot: &dyn PlotAccess, kind: PlotKind, id: usize) -> Self {
+        match kind {
+            PlotKind::Boolean => PlotValue::Boolean(plot.boolean(id)),
+            PlotKind::Integer => PlotValue::Integer(plot.integer(id)),
+            PlotKind::Float => PlotValue::Float(plot.float(id)),
+        }
+    }
+
+    pub fn set(self, plot: &mut dyn PlotAccess, id: usize) -> Result<()> {
+        match self {
+            PlotValue::Boolean(value) => plot.set_boolean(id, value),
+            PlotValue::Integer(value) => plot.set_integer(id, value),
+            PlotValue::Float(value) => plot.set_float(id, value),
+        }
+    }
+
+    // Integers are accepted for float variables
+    fn as_kind(self, kind: PlotKind) -> Option<Self> {
+        match (kind, self) {
+            (PlotKind::Boolean, PlotValue::Boolean(_))
+            | (PlotKind::Integer, PlotValue::Integer(_))
+            | (PlotKind::Float, PlotValue::Float(_)) => Some(self),
+            (PlotKind::Float, PlotValue::Integer(value)) => Some(PlotValue::Float(value as f32)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PlotValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlotValue::Boolean(value) => value.fmt(f),
+            PlotValue::Integer(value) => value.fmt(f),
+            PlotValue::Float(value) => value.fmt(f),
+        }
+    }
+}
+
+pub struct Mismatch {
+    // Index of the change in the patch
+    pub change: usize,
+    pub table: PlotGame,
+    pub kind: PlotKind,
+    pub id: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Mismatch {
+            change,
+            table,
+            kind,
+            id,
+            ref reason,
+        } = *self;
+        write!(f, "change #{change} ({table} {kind} {id}): {reason}")
+    }
+}
+
+impl PlotPatch {
+    pub fn from_toml(input: &str) -> Result<Self> {
+        Ok(toml::from_str(input)?)
+    }
+
+    // Every change is checked against the current values before anything is modified,
+    // so either the whole patch is applied or the tables are left untouched. A variable can only
+    // be changed once per patch
+    pub fn apply(&self, tables: &mut [(PlotGame, &mut dyn PlotAccess)]) -> Result<(), Vec<Mismatch>> {
+        let mut updates = Vec::with_capacity(self.changes.len());
+        let mut mismatches = Vec::new();
+
+        for (i, change) in self.changes.iter().enumerate() {
+            let PlotChange {
+                table,
+                kind,
+                id,
+                value,
+                expected,
+            } = *change;
+            let mut mismatch = |reason| {
+                mismatches.push(Mismatch {
+                    change: i,
+                    table,
+                    kind,
+                    id,
+                    reason,
+                })
+            };
+
+            if let Err(err) = check_plot_id(id) {
+                mismatch(err.to_string());
+                continue;
+            }
+
+            let same_variable = |other: &PlotChange| other.table == table && other.kind == kind && other.id == id;
+            if let Some(other) = self.changes[..i].iter().position(same_variable) {
+                mismatch(format!("already changed by change #{other}"));
+                continue;
+            }
+
+            let table_idx = match tables.iter().position(|(game, _)| *game == table) {
+                Some(table_idx) => table_idx,
+                None => {
+                    mismatch(format!("this save has no {table} plot table"));
+                    continue;
+                }
+            };
+
+            let value = match value.as_kind(kind) {
+                Some(value) => value,
+                None => {
+                    mismatch(format!("`{value}` is not a valid {kind} value"));
+                    continue;
+                }
+            };
+
+            if let Some(expected) = expected {
+                let current = PlotValue::get(&*tables[table_idx].1, kind, id);
+                match expected.as_kind(kind) {
+                    Some(expected) if expected == current => (),
+                    Some(expected) => {
+                        mismatch(format!("expected {expected}, found {current}"));
+                        continue;
+                    }
+                    None => {
+                        mismatch(format!("`{expected}` is not a valid {kind} value"));
+                        continue;
+                    }
+                }
+            }
+
+            updates.push((table_idx, id, value));
+        }
+
+        if !mismatches.is_empty() {
+            return Err(mismatches);
+        }
+
+        for (table_idx, id, value) in updates {
+            // The id was checked above, it is the only reason a setter can fail
+            value
+                .set(&mut *tables[table_idx].1, id)
+                .expect("Plot id checked before the update");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::{
+        save_data::{mass_effect_2::Me2SaveGame, mass_effect_3::Me3SaveGame},
+        unreal,
+    };
+
+    fn load_me2() -> Result<Me2SaveGame> {
+        let input = fs::read("test/ME2Save.pcsav")?;
+        Ok(unreal::Deserializer::from_bytes(&input)?)
+    }
+
+    fn tables(me2: &mut Me2SaveGame) -> [(PlotGame, &mut dyn PlotAccess); 2] {
+        [(PlotGame::Me2, &mut me2.plot), (PlotGame::Me1, &mut me2.me1_plot)]
+    }
+
+    #[test]
+    fn apply() -> Result<()> {
+        let mut me2 = load_me2()?;
+        let patch: PlotPatch = ron::from_str(
+            r#"(
+                changes: [
+                    (table: me2, kind: bool, id: 42, value: false, expected: Some(true)),
+                    (table: me1, kind: int, id: 3, value: 7),
+                    (table: me2, kind: float, id: 10000, value: 2),
+                ],
+            )"#,
+        )?;
+
+        assert!(patch.apply(&mut tables(&mut me2)).is_ok());
+        assert!(!me2.plot.boolean(42));
+        assert_eq!(me2.me1_plot.integer(3), 7);
+        assert_eq!(me2.plot.float(10000), 2.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn all_or_nothing() -> Result<()> {
+        let mut me2 = load_me2()?;
+        let patch = PlotPatch::from_toml(
+            r#"
+                [[changes]]
+                table = "me1"
+                kind = "int"
+                id = 3
+                value = 7
+
+                [[changes]]
+                table = "me2"
+                kind = "bool"
+                id = 42
+                value = true
+                expected = false
+
+                [[changes]]
+                table = "me3"
+                kind = "bool"
+                id = 1
+                value = true
+            "#,
+        )?;
+
+        let mismatches = patch.apply(&mut tables(&mut me2)).err().unwrap();
+        assert_eq!(
+            mismatches.iter().map(|mismatch| mismatch.change).collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert_eq!(
+            mismatches[0].to_string(),
+            "change #1 (me2 bool 42): expected false, found true"
+        );
+        assert_ne!(me2.me1_plot.integer(3), 7);
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate() -> Result<()> {
+        let mut me2 = load_me2()?;
+        let patch: PlotPatch = ron::from_str(
+            r#"(
+                changes: [
+                    (table: me2, kind: int, id: 3, value: 1),
+                    (table: me1, kind: int, id: 3, value: 2),
+                    (table: me2, kind: int, id: 3, value: 3, expected: Some(1)),
+                ],
+            )"#,
+        )?;
+        let before = me2.plot.integer(3);
+
+        let mismatches = patch.apply(&mut tables(&mut me2)).err().unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].to_string(), "change #2 (me2 int 3): already changed by change #0");
+        assert_eq!(me2.plot.integer(3), before);
+
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range() -> Result<()> {
+        let mut me2 = load_me2()?;
+        let patch = PlotPatch::from_toml(
+            r#"
+                [[changes]]
+                table = "me2"
+                kind = "bool"
+                id = 4000000000
+                value = true
+            "#,
+        )?;
+
+        let mismatches = patch.apply(&mut tables(&mut me2)).err().unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert!(me2.plot.booleans.len() < 4000000000);
+
+        // ME3 integers and floats are stored with an i32 id
+        let input = fs::read("test/ME3Save.pcsav")?;
+        let mut me3: Me3SaveGame = unreal::Deserializer::from_bytes(&input)?;
+        let id = i32::MAX as usize + 1;
+        assert!(me3.plot.set_integer(id, 1).is_err() && me3.plot.set_float(id, 1.0).is_err());
+        assert_eq!(me3.plot.integer(id), 0);
+        assert!(me3.plot.ids(PlotKind::Integer).iter().all(|&id| id <= i32::MAX as usize));
+
+        Ok(())
+    }
+}
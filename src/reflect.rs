@@ -0,0 +1,606 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Context, Error, Result};
+use serde_yaml::Value;
+
+use crate::save_game::SaveKind;
+
+// Generic access to any field of a save, through its human-readable serde representation.
+// Enum wrappers (the game of the save, legacy object classes) are transparent.
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+    // A map key that is not a valid field name, e.g. `["BIOA_NOR10"]`
+    Key(String),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FieldPath(pub Vec<PathSegment>);
+
+impl FromStr for FieldPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut rest = path.trim();
+
+        while !rest.is_empty() {
+            if let Some(bracket) = rest.strip_prefix('[') {
+                let end = bracket.find(']').with_context(|| format!("Missing `]` in `{path}`"))?;
+                let inner = bracket[..end].trim();
+                let segment = if let Some(key) = inner.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+                    PathSegment::Key(key.to_owned())
+                } else {
+                    let idx = inner
+                        .parse()
+                        .with_context(|| format!("Invalid index `{inner}` in `{path}`"))?;
+                    PathSegment::Index(idx)
+                };
+                segments.push(segment);
+                rest = &bracket[end + 1..];
+            } else {
+                if !segments.is_empty() {
+                    rest = rest
+                        .strip_prefix('.')
+                        .with_context(|| format!("Expected `.` or `[` in `{path}`, found `{rest}`"))?;
+                }
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let field = &rest[..end];
+                if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    bail!("Invalid field name `{field}` in `{path}`");
+                }
+                segments.push(PathSegment::Field(field.to_owned()));
+                rest = &rest[end..];
+            }
+        }
+
+        Ok(FieldPath(segments))
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Field(field) if i == 0 => f.write_str(field)?,
+                PathSegment::Field(field) => write!(f, ".{field}")?,
+                PathSegment::Index(idx) => write!(f, "[{idx}]")?,
+                PathSegment::Key(key) => write!(f, "[\"{key}\"]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn untagged(mut value: &Value) -> &Value {
+    while let Value::Tagged(tagged) = value {
+        value = &tagged.value;
+    }
+    value
+}
+
+fn untagged_mut(mut value: &mut Value) -> &mut Value {
+    while let Value::Tagged(tagged) = value {
+        value = &mut tagged.value;
+    }
+    value
+}
+
+fn is_key(key: &Value, segment: &PathSegment) -> bool {
+    match (segment, key) {
+        (PathSegment::Field(name) | PathSegment::Key(name), Value::String(key)) => key == name,
+        (PathSegment::Index(idx), Value::Number(key)) => key.as_u64() == Some(*idx as u64),
+        (PathSegment::Index(idx), Value::String(key)) => key.parse() == Ok(*idx),
+        _ => false,
+    }
+}
+
+fn child<'a>(value: &'a Value, segment: &PathSegment) -> Option<&'a Value> {
+    match (untagged(value), segment) {
+        (Value::Sequence(seq), PathSegment::Index(idx)) => seq.get(*idx),
+        (Value::Mapping(map), _) => map.iter().find(|(key, _)| is_key(key, segment)).map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, segment: &PathSegment) -> Option<&'a mut Value> {
+    match (untagged_mut(value), segment) {
+        (Value::Sequence(seq), PathSegment::Index(idx)) => seq.get_mut(*idx),
+        (Value::Mapping(map), _) => map
+            .iter_mut()
+            .find(|(key, _)| is_key(key, segment))
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "none",
+        Value::Bool(_) => "a boolean",
+        Value::Number(number) if number.is_f64() => "a float",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Sequence(_) => "a list",
+        Value::Mapping(_) => "a struct",
+        // An enum variant, named after its content
+        Value::Tagged(tagged) => type_name(&tagged.value),
+    }
+}
+
+// Parse `input` as a value of the same type as `current`
+fn parse_as(current: &Value, input: &str) -> Result<Value> {
+    let parsed: Value = serde_yaml::from_str(input).with_context(|| format!("Invalid value `{input}`"))?;
+
+    let value = match (untagged(current), parsed) {
+        (Value::Bool(_), parsed @ Value::Bool(_)) => parsed,
+        (Value::Number(current), Value::Number(parsed)) if !current.is_f64() => {
+            if parsed.is_f64() {
+                bail!("Expected an integer, found the float `{input}`");
+            }
+            Value::Number(parsed)
+        }
+        (Value::Number(_), Value::Number(parsed)) => Value::from(parsed.as_f64().unwrap_or_default()),
+        // Strings and unit enum variants are taken as is, `123` or `true` can be a string
+        (Value::String(_), _) => Value::String(input.to_owned()),
+        // An absent optional value, its type is only known once deserialized
+        (Value::Null, parsed) => parsed,
+        (Value::Sequence(_) | Value::Mapping(_), _) => {
+            bail!("Only a single value can be set, this is {}", type_name(current))
+        }
+        (_, parsed) => bail!("Expected {}, found {}", type_name(current), type_name(&parsed)),
+    };
+    Ok(value)
+}
+
+pub fn to_value(save_game: &SaveKind) -> Result<Value> {
+    Ok(serde_yaml::to_value(save_game)?)
+}
+
+// The platform is not part of the tree, it is given back
+pub fn from_value(root: Value, is_xbox360: bool) -> Result<SaveKind> {
+    let mut save_game: SaveKind = serde_yaml::from_value(root)?;
+    save_game.set_xbox360(is_xbox360);
+    Ok(save_game)
+}
+
+pub fn get<'a>(root: &'a Value, path: &FieldPath) -> Result<&'a Value> {
+    let mut value = root;
+    for (i, segment) in path.0.iter().enumerate() {
+        value = child(value, segment).with_context(|| format!("No field `{}`", FieldPath(path.0[..=i].to_vec())))?;
+    }
+    Ok(untagged(value))
+}
+
+pub fn get_field(save_game: &SaveKind, path: &FieldPath) -> Result<Value> {
+    get(&to_value(save_game)?, path).cloned()
+}
+
+fn replace_with(root: &mut Value, path: &FieldPath, new_value: impl FnOnce(&Value) -> Result<Value>) -> Result<Value> {
+    let mut value = root;
+    for (i, segment) in path.0.iter().enumerate() {
+        value =
+            child_mut(value, segment).with_context(|| format!("No field `{}`", FieldPath(path.0[..=i].to_vec())))?;
+    }
+    let value = untagged_mut(value);
+
+    let new_value = new_value(value)?;
+    Ok(std::mem::replace(value, new_value))
+}
+
+// Edits of a tree, the types are checked against the current values but the tree is only known to be
+// a valid save once deserialized, returns the old value
+pub fn set(root: &mut Value, path: &FieldPath, input: &str) -> Result<Value> {
+    replace_with(root, path, |current| parse_as(current, input))
+        .with_context(|| format!("Cannot set `{path}` to `{input}`"))
+}
+
+// Without type checking, for a value that comes from a save of the same game
+pub fn replace(root: &mut Value, path: &FieldPath, value: Value) -> Result<Value> {
+    replace_with(root, path, |_| Ok(value)).with_context(|| format!("Cannot set `{path}`"))
+}
+
+// Edits the tree of a save with `set` or `replace`, the save is only modified if the edited tree is
+// still a valid save, returns what `f` returns
+pub fn edit<T>(save_game: &mut SaveKind, f: impl FnOnce(&mut Value) -> Result<T>) -> Result<T> {
+    let mut root = to_value(save_game)?;
+    let result = f(&mut root)?;
+    *save_game = from_value(root, save_game.is_xbox360()).context("The edited save is not valid")?;
+    Ok(result)
+}
+
+fn key_segment(key: &Value) -> PathSegment {
+    match key {
+        Value::String(key) if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+            PathSegment::Field(key.to_owned())
+        }
+        Value::String(key) => PathSegment::Key(key.to_owned()),
+        Value::Number(key) if key.is_u64() => PathSegment::Index(key.as_u64().unwrap_or_default() as usize),
+        key => PathSegment::Key(serde_yaml::to_string(key).unwrap_or_default().trim_end().to_owned()),
+    }
+}
+
+// Fields, map keys or indices of a list or a struct
+pub fn children(value: &Value) -> Vec<(PathSegment, &Value)> {
+    match untagged(value) {
+        Value::Sequence(seq) => seq
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (PathSegment::Index(i), value))
+            .collect(),
+        Value::Mapping(map) => map.iter().map(|(key, value)| (key_segment(key), value)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Change {
+    Added(FieldPath, Value),
+    Removed(FieldPath, Value),
+    Modified(FieldPath, Value, Value),
+}
+
+impl Change {
+    pub fn path(&self) -> &FieldPath {
+        match self {
+            Change::Added(path, _) | Change::Removed(path, _) | Change::Modified(path, ..) => path,
+        }
+    }
+}
+
+// Every scalar that differs, and the list elements or map entries that only exist on one side
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    fn diff_at(path: &mut Vec<PathSegment>, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+        let tag = |value: &Value| match value {
+            Value::Tagged(tagged) => Some(tagged.tag.to_string()),
+            _ => None,
+        };
+        if tag(old) != tag(new) {
+            changes.push(Change::Modified(FieldPath(path.clone()), old.clone(), new.clone()));
+            return;
+        }
+
+        match (untagged(old), untagged(new)) {
+            (Value::Sequence(old), Value::Sequence(new)) => {
+                for (i, (old, new)) in old.iter().zip(new).enumerate() {
+                    path.push(PathSegment::Index(i));
+                    diff_at(path, old, new, changes);
+                    path.pop();
+                }
+                for (i, old) in old.iter().enumerate().skip(new.len()) {
+                    path.push(PathSegment::Index(i));
+                    changes.push(Change::Removed(FieldPath(path.clone()), old.clone()));
+                    path.pop();
+                }
+                for (i, new) in new.iter().enumerate().skip(old.len()) {
+                    path.push(PathSegment::Index(i));
+                    changes.push(Change::Added(FieldPath(path.clone()), new.clone()));
+                    path.pop();
+                }
+            }
+            (Value::Mapping(old), Value::Mapping(new)) => {
+                for (key, old) in old {
+                    path.push(key_segment(key));
+                    match new.get(key) {
+                        Some(new) => diff_at(path, old, new, changes),
+                        None => changes.push(Change::Removed(FieldPath(path.clone()), old.clone())),
+                    }
+                    path.pop();
+                }
+                for (key, new) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                    path.push(key_segment(key));
+                    changes.push(Change::Added(FieldPath(path.clone()), new.clone()));
+                    path.pop();
+                }
+            }
+            (old_value, new_value) if old_value != new_value => {
+                changes.push(Change::Modified(FieldPath(path.clone()), old.clone(), new.clone()))
+            }
+            _ => (),
+        }
+    }
+
+    let mut changes = Vec::new();
+    diff_at(&mut Vec::new(), old, new, &mut changes);
+    changes
+}
+
+// Path of the first field named `name`, breadth first, lists are not searched
+pub fn find_field(root: &Value, name: &str) -> Option<FieldPath> {
+    let mut queue = vec![(Vec::new(), root)];
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for (path, value) in queue {
+            if let Value::Mapping(_) = untagged(value) {
+                for (segment, child) in children(value) {
+                    let mut child_path = path.clone();
+                    child_path.push(segment.clone());
+                    if segment == PathSegment::Field(name.to_owned()) {
+                        return Some(FieldPath(child_path));
+                    }
+                    next.push((child_path, child));
+                }
+            }
+        }
+        queue = next;
+    }
+    None
+}
+
+// `None` is an absent map entry
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    pub path: FieldPath,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+// Three-way merge, structs and lists of the same length are merged field by field,
+// a field changed differently on both sides is a conflict resolved with `ours` or `theirs`
+pub fn merge(base: &Value, ours: &Value, theirs: &Value, prefer_theirs: bool) -> (Value, Vec<Conflict>) {
+    fn merge_at(
+        path: &mut Vec<PathSegment>,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+        prefer_theirs: bool,
+        conflicts: &mut Vec<Conflict>,
+    ) -> Option<Value> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+
+        let tag = |value: &Value| match value {
+            Value::Tagged(tagged) => Some(tagged.tag.clone()),
+            _ => None,
+        };
+        if let (Some(base), Some(ours), Some(theirs)) = (base, ours, theirs) {
+            let is_same_tag = tag(ours) == tag(base) && tag(theirs) == tag(base);
+            let merged = match (untagged(base), untagged(ours), untagged(theirs)) {
+                (Value::Mapping(base), Value::Mapping(ours), Value::Mapping(theirs)) if is_same_tag => {
+                    let keys = ours.keys().chain(theirs.keys().filter(|key| !ours.contains_key(*key)));
+                    let mut merged = serde_yaml::Mapping::new();
+                    for key in keys {
+                        path.push(key_segment(key));
+                        let value = merge_at(
+                            path,
+                            base.get(key),
+                            ours.get(key),
+                            theirs.get(key),
+                            prefer_theirs,
+                            conflicts,
+                        );
+                        path.pop();
+                        if let Some(value) = value {
+                            merged.insert(key.clone(), value);
+                        }
+                    }
+                    Some(Value::Mapping(merged))
+                }
+                (Value::Sequence(base), Value::Sequence(ours), Value::Sequence(theirs))
+                    if is_same_tag && ours.len() == base.len() && theirs.len() == base.len() =>
+                {
+                    let mut merged = Vec::with_capacity(base.len());
+                    for (i, ((base, ours), theirs)) in base.iter().zip(ours).zip(theirs).enumerate() {
+                        path.push(PathSegment::Index(i));
+                        merged.extend(merge_at(
+                            path,
+                            Some(base),
+                            Some(ours),
+                            Some(theirs),
+                            prefer_theirs,
+                            conflicts,
+                        ));
+                        path.pop();
+                    }
+                    Some(Value::Sequence(merged))
+                }
+                _ => None,
+            };
+
+            if let Some(merged) = merged {
+                return Some(match ours {
+                    Value::Tagged(tagged) => Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+                        tag: tagged.tag.clone(),
+                        value: merged,
+                    })),
+                    _ => merged,
+                });
+            }
+        }
+
+        conflicts.push(Conflict {
+            path: FieldPath(path.clone()),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        });
+        if prefer_theirs {
+            theirs.cloned()
+        } else {
+            ours.cloned()
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    let merged = merge_at(
+        &mut Vec::new(),
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        prefer_theirs,
+        &mut conflicts,
+    );
+    (merged.unwrap_or(Value::Null), conflicts)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::save_game::{Checksum, SaveGame};
+
+    #[test]
+    fn parse_path() -> Result<()> {
+        let path: FieldPath = "galaxy_map.planets[5].visited".parse()?;
+        assert_eq!(
+            path.0,
+            [
+                PathSegment::Field("galaxy_map".to_owned()),
+                PathSegment::Field("planets".to_owned()),
+                PathSegment::Index(5),
+                PathSegment::Field("visited".to_owned()),
+            ]
+        );
+        assert_eq!(path.to_string(), "galaxy_map.planets[5].visited");
+
+        let path: FieldPath = r#"levels["BIOA_NOR10"].should_be_loaded"#.parse()?;
+        assert_eq!(path.0[1], PathSegment::Key("BIOA_NOR10".to_owned()));
+
+        assert!("player..credits".parse::<FieldPath>().is_err());
+        assert!("squad[two]".parse::<FieldPath>().is_err());
+        assert!("squad[2".parse::<FieldPath>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_set() -> Result<()> {
+        let mut save_game = SaveKind::deserialize(fs::read("test/ME2Save.pcsav")?)?;
+
+        let credits: FieldPath = "player.credits".parse()?;
+        let old_credits = edit(&mut save_game, |root| set(root, &credits, "123456"))?;
+        assert_eq!(save_game.credits(), Some(123456));
+        assert_ne!(old_credits, Value::from(123456));
+        assert_eq!(get_field(&save_game, &credits)?, Value::from(123456));
+
+        // A float in an `i32`
+        assert!(edit(&mut save_game, |root| set(root, &credits, "1.5")).is_err());
+        // Out of range
+        assert!(edit(&mut save_game, |root| set(root, &"squad[2].talent_points".parse()?, "9999999999")).is_err());
+        assert!(edit(&mut save_game, |root| set(root, &"player.no_such_field".parse()?, "1")).is_err());
+        assert_eq!(save_game.credits(), Some(123456));
+
+        // Enum variants with content are tagged, named after their content
+        let tagged: Value = serde_yaml::from_str("!Known 1")?;
+        assert_eq!(type_name(&tagged), "an integer");
+
+        let visited: FieldPath = "galaxy_map.planets[5].visited".parse()?;
+        edit(&mut save_game, |root| set(root, &visited, "true"))?;
+        assert_eq!(get_field(&save_game, &visited)?, Value::Bool(true));
+
+        // Integers are accepted for floats
+        let seconds_played: FieldPath = "seconds_played".parse()?;
+        edit(&mut save_game, |root| set(root, &seconds_played, "3600"))?;
+        assert_eq!(get_field(&save_game, &seconds_played)?, Value::from(3600.0));
+
+        // The edited save is still a valid save
+        let output = save_game.serialize_for_platform(false)?;
+        let save_game = SaveKind::deserialize(output.clone())?;
+        assert!(Checksum::read(&output).unwrap().is_valid());
+        assert_eq!(save_game.credits(), Some(123456));
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_and_undo() -> Result<()> {
+        let mut save_game = SaveKind::deserialize(fs::read("test/ME3Save.pcsav")?)?;
+        let original = to_value(&save_game)?;
+
+        let credits: FieldPath = "player.credits".parse()?;
+        let old_credits = edit(&mut save_game, |root| set(root, &credits, "42"))?;
+        edit(&mut save_game, |root| set(root, &"galaxy_map.planets[0].visited".parse()?, "false"))?;
+
+        let changes = diff(&original, &to_value(&save_game)?);
+        let paths: Vec<_> = changes.iter().map(|change| change.path().to_string()).collect();
+        assert!(paths.contains(&"player.credits".to_owned()), "{paths:?}");
+        assert!(matches!(changes[0], Change::Modified(_, _, ref new) if new.as_i64() == Some(42)));
+
+        edit(&mut save_game, |root| replace(root, &credits, old_credits))?;
+        let root = to_value(&save_game)?;
+        assert_eq!(diff(&original, &root).len(), changes.len() - 1);
+
+        let fields: Vec<_> = children(get(&root, &"player".parse()?)?)
+            .into_iter()
+            .map(|(segment, _)| segment)
+            .collect();
+        assert!(fields.contains(&PathSegment::Field("credits".to_owned())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn three_way_merge() -> Result<()> {
+        let base: Value = serde_yaml::from_str("{ a: 1, b: [1, 2], c: { d: true }, e: x }")?;
+        let ours: Value = serde_yaml::from_str("{ a: 2, b: [1, 3], c: { d: true }, e: y }")?;
+        let theirs: Value = serde_yaml::from_str("{ a: 1, b: [4, 2], c: { d: false }, e: z }")?;
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs, false);
+        let expected: Value = serde_yaml::from_str("{ a: 2, b: [4, 3], c: { d: false }, e: y }")?;
+        assert_eq!(merged, expected);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path.to_string(), "e");
+
+        let (merged, _) = merge(&base, &ours, &theirs, true);
+        assert_eq!(get(&merged, &"e".parse()?)?, &Value::from("z"));
+
+        let save_game = SaveKind::deserialize(fs::read("test/ME1LeSave.pcsav")?)?;
+        let root = to_value(&save_game)?;
+        assert_eq!(
+            find_field(&root, "journal").map(|path| path.to_string()).as_deref(),
+            Some("save_data.journal")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lossless() -> Result<()> {
+        let files = [
+            "test/ME1Save.MassEffectSave",
+            "test/ME1LeSave.pcsav",
+            "test/ME1LeExport.pcsav",
+            "test/ME2Save.pcsav",
+            "test/ME2Save360.xbsav",
+            "test/ME2LeSave.pcsav",
+            "test/ME3Save.pcsav",
+            "test/ME3Save360.xbsav",
+        ];
+        for file in files {
+            let save_game = SaveKind::deserialize(fs::read(file)?)?;
+            let is_xbox360 = save_game.is_xbox360();
+            let expected = save_game.serialize_for_platform(is_xbox360)?;
+
+            let save_game = from_value(to_value(&save_game)?, is_xbox360)?;
+            assert!(save_game.serialize_for_platform(is_xbox360)? == expected, "{file}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_keeps_platform() -> Result<()> {
+        for file in ["test/ME2Save360.xbsav", "test/ME3Save360.xbsav"] {
+            let mut save_game = SaveKind::deserialize(fs::read(file)?)?;
+            assert!(save_game.is_xbox360());
+
+            edit(&mut save_game, |root| set(root, &"player.credits".parse()?, "4242"))?;
+            assert!(save_game.is_xbox360(), "{file}");
+
+            // Written and read back as an Xbox 360 save
+            let output = save_game.serialize_for_platform(save_game.is_xbox360())?;
+            let save_game = SaveKind::deserialize(output.clone())?;
+            assert!(save_game.is_xbox360(), "{file}");
+            assert!(Checksum::read(&output).unwrap().is_valid(), "{file}");
+            assert_eq!(save_game.credits(), Some(4242), "{file}");
+        }
+        Ok(())
+    }
+}
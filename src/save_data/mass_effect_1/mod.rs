@@ -0,0 +1,279 @@
+pub mod package;
+pub mod player;
+pub mod state;
+
+use self::{package::Package, state::*};
+
+use std::{
+    fmt,
+    io::{Cursor, Read, Write},
+};
+
+use anyhow::{Context, Result};
+use serde::{
+    de,
+    ser::{self, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use zip::{result::ZipError, write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    save_data::{next_element, List},
+    unreal,
+};
+
+const PLAYER_FILE: &str = "player.sav";
+const STATE_FILE: &str = "state.sav";
+const WORLD_SAVE_PACKAGE_FILE: &str = "WorldSavePackage.sav";
+
+// The save is a header followed by a zip containing :
+// - player.sav : Unreal package with the player, the squad and the head morph
+// - state.sav : plot, journal, codex, etc.
+// - WorldSavePackage.sav : Unreal package with the world state, kept as is (not in character exports)
+pub struct Me1SaveGame {
+    magic_number: Me1MagicNumber,
+    _version: u32,
+    zip_offset: u32,
+    _no_mans_land: List<u8>,
+    pub player: Package,
+    pub state: State,
+    _world_save_package: Option<List<u8>>,
+}
+
+impl Me1SaveGame {
+    // The header followed by the unzipped files, to compare saves regardless of the compression
+    pub fn unzip(input: &[u8]) -> Result<Vec<u8>> {
+        let zip_offset = input.get(8..12).context("Unexpected end of file")?;
+        let zip_offset = u32::from_le_bytes([zip_offset[0], zip_offset[1], zip_offset[2], zip_offset[3]]) as usize;
+        let (header, zip) = input.split_at(zip_offset.min(input.len()));
+
+        let mut output = header.to_vec();
+        let mut zip = ZipArchive::new(Cursor::new(zip))?;
+        for name in [PLAYER_FILE, STATE_FILE, WORLD_SAVE_PACKAGE_FILE] {
+            match zip.by_name(name) {
+                Ok(mut file) => file.read_to_end(&mut output)?,
+                Err(ZipError::FileNotFound) => continue,
+                Err(err) => return Err(err.into()),
+            };
+        }
+        Ok(output)
+    }
+}
+
+impl<'de> Deserialize<'de> for Me1SaveGame {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            // Unzipped form, the zip is rebuilt on serialization
+            #[derive(Deserialize)]
+            #[serde(rename = "Me1SaveGame")]
+            struct HumanReadable {
+                magic_number: Me1MagicNumber,
+                _version: u32,
+                zip_offset: u32,
+                _no_mans_land: List<u8>,
+                player: Package,
+                state: State,
+                _world_save_package: Option<List<u8>>,
+            }
+
+            let HumanReadable {
+                magic_number,
+                _version,
+                zip_offset,
+                _no_mans_land,
+                player,
+                state,
+                _world_save_package,
+            } = Deserialize::deserialize(deserializer)?;
+
+            return Ok(Me1SaveGame {
+                magic_number,
+                _version,
+                zip_offset,
+                _no_mans_land,
+                player,
+                state,
+                _world_save_package,
+            });
+        }
+
+        struct Me1SaveGameVisitor;
+        impl<'de> de::Visitor<'de> for Me1SaveGameVisitor {
+            type Value = Me1SaveGame;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a Me1SaveGame")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut len = 0;
+                let magic_number = next_element(&mut seq, &mut len, &self)?;
+                let version = next_element(&mut seq, &mut len, &self)?;
+                let zip_offset: u32 = next_element(&mut seq, &mut len, &self)?;
+
+                // Garbage between the header and the zip
+                let mut no_mans_land = Vec::new();
+                for _ in 12..zip_offset {
+                    no_mans_land.push(next_element(&mut seq, &mut len, &self)?);
+                }
+
+                // Zip
+                let zip: List<u8> = next_element(&mut seq, &mut len, &self)?;
+                let mut zip = ZipArchive::new(Cursor::new(&zip[..])).map_err(de::Error::custom)?;
+
+                let mut unzip = |name: &str| -> Result<Option<Vec<u8>>, A::Error> {
+                    let mut file = match zip.by_name(name) {
+                        Ok(file) => file,
+                        Err(ZipError::FileNotFound) => return Ok(None),
+                        Err(err) => return Err(de::Error::custom(err)),
+                    };
+
+                    let mut bytes = Vec::new();
+                    file.read_to_end(&mut bytes).map_err(de::Error::custom)?;
+                    Ok(Some(bytes))
+                };
+
+                let player = unzip(PLAYER_FILE)?.ok_or_else(|| de::Error::missing_field(PLAYER_FILE))?;
+                let state = unzip(STATE_FILE)?.ok_or_else(|| de::Error::missing_field(STATE_FILE))?;
+                let world_save_package = unzip(WORLD_SAVE_PACKAGE_FILE)?;
+
+                let player = Package::from_bytes(&player).map_err(de::Error::custom)?;
+                let state: State = unreal::Deserializer::from_bytes(&state).map_err(|err: unreal::Error| {
+                    err.nested(unreal::Nested::Extracted {
+                        field: "state",
+                        within: "the zipped state",
+                    })
+                })?;
+
+                Ok(Me1SaveGame {
+                    magic_number,
+                    _version: version,
+                    zip_offset,
+                    _no_mans_land: no_mans_land.into(),
+                    player,
+                    state,
+                    _world_save_package: world_save_package.map(List::from),
+                })
+            }
+        }
+        deserializer.deserialize_tuple_struct("Me1SaveGame", usize::MAX, Me1SaveGameVisitor)
+    }
+}
+
+impl serde::Serialize for Me1SaveGame {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let Me1SaveGame {
+            magic_number,
+            _version,
+            zip_offset,
+            _no_mans_land,
+            player,
+            state,
+            _world_save_package,
+        } = self;
+
+        if serializer.is_human_readable() {
+            let mut s = serializer.serialize_struct("Me1SaveGame", 7)?;
+            s.serialize_field("magic_number", magic_number)?;
+            s.serialize_field("_version", _version)?;
+            s.serialize_field("zip_offset", zip_offset)?;
+            s.serialize_field("_no_mans_land", _no_mans_land)?;
+            s.serialize_field("player", player)?;
+            s.serialize_field("state", state)?;
+            s.serialize_field("_world_save_package", _world_save_package)?;
+            return s.end();
+        }
+
+        // Zip
+        let zip: List<u8> = {
+            let player = player.to_bytes().map_err(ser::Error::custom)?;
+            let state = unreal::Serializer::to_vec(state).map_err(ser::Error::custom)?;
+
+            let mut files = vec![(PLAYER_FILE, &player[..]), (STATE_FILE, &state[..])];
+            if let Some(world_save_package) = _world_save_package {
+                files.push((WORLD_SAVE_PACKAGE_FILE, &world_save_package[..]));
+            }
+
+            let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+            let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+            for (name, bytes) in files {
+                zip.start_file(name, options).map_err(ser::Error::custom)?;
+                zip.write_all(bytes).map_err(ser::Error::custom)?;
+            }
+            zip.finish().map_err(ser::Error::custom)?.into_inner().into()
+        };
+
+        let mut s = serializer.serialize_struct("Me1SaveGame", 5)?;
+        s.serialize_field("magic_number", magic_number)?;
+        s.serialize_field("version", _version)?;
+        s.serialize_field("zip_offset", zip_offset)?;
+        s.serialize_field("no_mans_land", _no_mans_land)?;
+        s.serialize_field("zip", &zip)?;
+        s.end()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
+pub struct Me1MagicNumber(u32);
+
+impl<'de> Deserialize<'de> for Me1MagicNumber {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const MAGIC_NUMBER: [u8; 4] = *b"RGMH";
+
+        if deserializer.is_human_readable() {
+            let magic_number: u32 = Deserialize::deserialize(deserializer)?;
+            if magic_number != u32::from_le_bytes(MAGIC_NUMBER) {
+                return Err(de::Error::custom("Wrong magic number"));
+            }
+            return Ok(Self(magic_number));
+        }
+
+        let magic_number: [u8; 4] = Deserialize::deserialize(deserializer)?;
+
+        if magic_number != MAGIC_NUMBER {
+            return Err(de::Error::custom("Wrong magic number"));
+        }
+
+        Ok(Self(u32::from_le_bytes(magic_number)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::unreal;
+
+    #[test]
+    fn deserialize_serialize() -> Result<()> {
+        let input = fs::read("test/ME1Save.MassEffectSave")?;
+
+        // Deserialize
+        let me1_save_game: Me1SaveGame = unreal::Deserializer::from_bytes(&input)?;
+
+        // Serialize
+        let output = unreal::Serializer::to_vec(&me1_save_game)?;
+
+        // Check the header, player.sav, state.sav and WorldSavePackage.sav are rebuilt as is, the zip
+        // itself depends on the compression
+        assert!(Me1SaveGame::unzip(&input)? == Me1SaveGame::unzip(&output)?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,981 @@
+use std::collections::HashMap;
+
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    save_data::{
+        shared::{appearance::LinearColor, Rotator, Vector3D},
+        Dummy, List,
+    },
+    unreal,
+};
+
+const PACKAGE_TAG: u32 = 0x9E2A83C1;
+const NONE: &str = "None";
+
+// Unreal package (player.sav) : a name table, the imports (objects of the game files) and the exports
+// (objects of the save, made of properties). Names are resolved to strings, `Name_N` being the name
+// `Name` with the number N + 1, and the offsets and sizes are recomputed on serialization.
+// Object references are export indices starting at 1, negative import indices or 0 for none.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Package {
+    _header: List<u8>,
+    _version: u16,
+    _licensee_version: u16,
+    _folder_name: String,
+    _package_flags: u32,
+    _guid: Dummy<16>,
+    _generations: Vec<Generation>,
+    _engine_version: i32,
+    _cooker_version: i32,
+    _unknown: List<u8>,
+    _names: Vec<NameEntry>,
+    pub imports: Vec<Import>,
+    _depends: List<u8>,
+    pub exports: Vec<Export>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct Generation {
+    export_count: i32,
+    name_count: i32,
+    net_object_count: i32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct NameEntry {
+    name: String,
+    flags: u64,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Import {
+    pub class_package: String,
+    pub class: String,
+    pub outer: i32,
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Export {
+    pub class: i32,
+    _super_class: i32,
+    pub outer: i32,
+    pub name: String,
+    _archetype: i32,
+    _object_flags: u64,
+    _component_map: Vec<(String, i32)>,
+    _export_flags: u32,
+    _net_objects: Vec<i32>,
+    _package_guid: Dummy<16>,
+    _package_flags: u32,
+    pub data: ObjectData,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum ObjectData {
+    Properties {
+        net_index: i32,
+        properties: Vec<Property>,
+        // Native data after the properties, empty for the save objects
+        _remaining: List<u8>,
+    },
+    // Not a list of properties
+    Raw(List<u8>),
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Property {
+    pub name: String,
+    pub array_index: i32,
+    pub value: Value,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum Value {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Byte(u8),
+    Enum(String),
+    Name(String),
+    Object(i32),
+    Str(String),
+    StringRef(i32),
+    Struct(String, StructValue),
+    // The element type is not stored, it is guessed from the data
+    Array(ArrayValue),
+    // Property type and payload
+    Unknown(String, List<u8>),
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum StructValue {
+    Vector(Vector3D),
+    Rotator(Rotator),
+    LinearColor(LinearColor),
+    Properties(Vec<Property>),
+    Raw(List<u8>),
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum ArrayValue {
+    Structs(Vec<Vec<Property>>),
+    // Also object references
+    Ints(Vec<i32>),
+    Strings(Vec<String>),
+    Vectors(Vec<Vector3D>),
+    // Element count and data
+    Raw(i32, List<u8>),
+}
+
+impl Export {
+    pub fn properties(&self) -> &[Property] {
+        match &self.data {
+            ObjectData::Properties { properties, .. } => properties,
+            ObjectData::Raw(_) => &[],
+        }
+    }
+
+    pub fn properties_mut(&mut self) -> &mut [Property] {
+        match &mut self.data {
+            ObjectData::Properties { properties, .. } => properties,
+            ObjectData::Raw(_) => &mut [],
+        }
+    }
+}
+
+pub fn find<'a>(properties: &'a [Property], name: &str) -> Option<&'a Value> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .map(|property| &property.value)
+}
+
+pub fn find_mut<'a>(properties: &'a mut [Property], name: &str) -> Option<&'a mut Value> {
+    properties
+        .iter_mut()
+        .find(|property| property.name == name)
+        .map(|property| &mut property.value)
+}
+
+impl Package {
+    pub fn export(&self, index: i32) -> Option<&Export> {
+        self.exports.get(usize::try_from(index).ok()?.checked_sub(1)?)
+    }
+
+    pub fn export_mut(&mut self, index: i32) -> Option<&mut Export> {
+        self.exports.get_mut(usize::try_from(index).ok()?.checked_sub(1)?)
+    }
+
+    // First export of a class, e.g. `BioSaveGame`
+    pub fn export_of_class(&self, class: &str) -> Option<i32> {
+        let position = self.exports.iter().position(|export| {
+            export.class < 0 && self.import(export.class).map(|import| import.name.as_str()) == Some(class)
+        })?;
+        Some(position as i32 + 1)
+    }
+
+    pub fn import(&self, index: i32) -> Option<&Import> {
+        self.imports
+            .get(usize::try_from(index.checked_neg()?).ok()?.checked_sub(1)?)
+    }
+
+    // `Package.Group.Name` path of an import
+    pub fn import_path(&self, index: i32) -> Option<String> {
+        let import = self.import(index)?;
+        Some(match import.outer {
+            0 => import.name.clone(),
+            outer => format!("{}.{}", self.import_path(outer)?, import.name),
+        })
+    }
+
+    // Index of the import at `path`, the missing imports are added with the outers as packages
+    pub fn find_or_add_import(&mut self, path: &str, class_package: &str, class: &str) -> i32 {
+        let (outer, name) = match path.rsplit_once('.') {
+            Some((outer, name)) => (self.find_or_add_import(outer, "Core", "Package"), name),
+            None => (0, path),
+        };
+
+        let position = self
+            .imports
+            .iter()
+            .position(|import| import.outer == outer && import.name == name);
+        let position = position.unwrap_or_else(|| {
+            self.imports.push(Import {
+                class_package: class_package.to_owned(),
+                class: class.to_owned(),
+                outer,
+                name: name.to_owned(),
+            });
+            self.imports.len() - 1
+        });
+        -(position as i32 + 1)
+    }
+
+    pub fn from_bytes(input: &[u8]) -> Result<Self> {
+        // The package has a header of its own, followed by the package at the offset stored at byte 8
+        let mut reader = Reader::new(input, 8);
+        let package_offset = reader.u32()? as usize;
+        let header = input.get(..package_offset).context("Unexpected end of file")?;
+
+        let mut reader = Reader::new(input, package_offset);
+        ensure!(reader.u32()? == PACKAGE_TAG, "Not an Unreal package");
+        let version = reader.u16()?;
+        let licensee_version = reader.u16()?;
+        let data_offset = reader.i32()?;
+        let data_offset = reader.offset(data_offset)?;
+        let folder_name = reader.string()?;
+        let package_flags = reader.u32()?;
+        let name_count = reader.i32()?;
+        let name_offset = reader.i32()?;
+        let name_offset = reader.offset(name_offset)?;
+        let export_count = reader.i32()?;
+        let export_offset = reader.i32()?;
+        let export_offset = reader.offset(export_offset)?;
+        let import_count = reader.i32()?;
+        let import_offset = reader.i32()?;
+        let import_offset = reader.offset(import_offset)?;
+        let depends_offset = reader.i32()?;
+        let depends_offset = reader.offset(depends_offset)?;
+        let guid = Dummy(reader.array()?);
+        let generations = (0..reader.i32()?)
+            .map(|_| {
+                Ok(Generation {
+                    export_count: reader.i32()?,
+                    name_count: reader.i32()?,
+                    net_object_count: reader.i32()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let engine_version = reader.i32()?;
+        let cooker_version = reader.i32()?;
+        let unknown = reader.bytes(name_offset.saturating_sub(reader.position))?.into();
+
+        let mut reader = Reader::new(input, name_offset);
+        let names: Vec<_> = (0..name_count)
+            .map(|_| {
+                Ok(NameEntry {
+                    name: reader.string()?,
+                    flags: reader.u64()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let mut reader = Reader::new(input, import_offset);
+        let imports = (0..import_count)
+            .map(|_| {
+                Ok(Import {
+                    class_package: reader.name(&names)?,
+                    class: reader.name(&names)?,
+                    outer: reader.i32()?,
+                    name: reader.name(&names)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let mut reader = Reader::new(input, export_offset);
+        let exports = (0..export_count)
+            .map(|idx| read_export(&mut reader, input, &names).with_context(|| format!("Export {}", idx + 1)))
+            .collect::<Result<_>>()?;
+
+        let depends = input
+            .get(depends_offset..data_offset)
+            .context("Unexpected end of file")?
+            .into();
+
+        Ok(Package {
+            _header: header.into(),
+            _version: version,
+            _licensee_version: licensee_version,
+            _folder_name: folder_name,
+            _package_flags: package_flags,
+            _guid: guid,
+            _generations: generations,
+            _engine_version: engine_version,
+            _cooker_version: cooker_version,
+            _unknown: unknown,
+            _names: names,
+            imports,
+            _depends: depends,
+            exports,
+        })
+    }
+
+    // Names, imports, exports, depends and objects, in this order
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut names = NameTable::new(&self._names);
+
+        // Objects first, they may add names
+        let mut objects = Vec::new();
+        let mut object_sizes = Vec::new();
+        for export in &self.exports {
+            let len = objects.len();
+            write_object_data(&export.data, &mut names, &mut objects)?;
+            object_sizes.push(objects.len() - len);
+        }
+
+        let mut imports = Vec::new();
+        for import in &self.imports {
+            write_name(&import.class_package, &mut names, &mut imports);
+            write_name(&import.class, &mut names, &mut imports);
+            imports.extend(import.outer.to_le_bytes());
+            write_name(&import.name, &mut names, &mut imports);
+        }
+
+        // The object offsets are patched once the layout is known
+        let mut exports = Vec::new();
+        let mut offset_positions = Vec::new();
+        for (export, size) in self.exports.iter().zip(&object_sizes) {
+            exports.extend(export.class.to_le_bytes());
+            exports.extend(export._super_class.to_le_bytes());
+            exports.extend(export.outer.to_le_bytes());
+            write_name(&export.name, &mut names, &mut exports);
+            exports.extend(export._archetype.to_le_bytes());
+            exports.extend(export._object_flags.to_le_bytes());
+            exports.extend((*size as i32).to_le_bytes());
+            offset_positions.push(exports.len());
+            exports.extend(0_i32.to_le_bytes());
+            exports.extend((export._component_map.len() as i32).to_le_bytes());
+            for (name, object) in &export._component_map {
+                write_name(name, &mut names, &mut exports);
+                exports.extend(object.to_le_bytes());
+            }
+            exports.extend(export._export_flags.to_le_bytes());
+            exports.extend((export._net_objects.len() as i32).to_le_bytes());
+            for net_object in &export._net_objects {
+                exports.extend(net_object.to_le_bytes());
+            }
+            exports.extend(export._package_guid.0);
+            exports.extend(export._package_flags.to_le_bytes());
+        }
+
+        let names = names.entries;
+        let mut name_table = Vec::new();
+        for NameEntry { name, flags } in &names {
+            write_string(name, &mut name_table)?;
+            name_table.extend(flags.to_le_bytes());
+        }
+
+        let mut generations = self._generations.clone();
+        if let Some(generation) = generations.last_mut() {
+            generation.export_count = self.exports.len() as i32;
+            generation.name_count = names.len() as i32;
+        }
+
+        let write_header = |offsets: [usize; 5], output: &mut Vec<u8>| -> Result<()> {
+            let [name_offset, import_offset, export_offset, depends_offset, data_offset] = offsets;
+            output.extend(PACKAGE_TAG.to_le_bytes());
+            output.extend(self._version.to_le_bytes());
+            output.extend(self._licensee_version.to_le_bytes());
+            output.extend((data_offset as i32).to_le_bytes());
+            write_string(&self._folder_name, output)?;
+            output.extend(self._package_flags.to_le_bytes());
+            output.extend((names.len() as i32).to_le_bytes());
+            output.extend((name_offset as i32).to_le_bytes());
+            output.extend((self.exports.len() as i32).to_le_bytes());
+            output.extend((export_offset as i32).to_le_bytes());
+            output.extend((self.imports.len() as i32).to_le_bytes());
+            output.extend((import_offset as i32).to_le_bytes());
+            output.extend((depends_offset as i32).to_le_bytes());
+            output.extend(self._guid.0);
+            output.extend((generations.len() as i32).to_le_bytes());
+            for generation in &generations {
+                output.extend(generation.export_count.to_le_bytes());
+                output.extend(generation.name_count.to_le_bytes());
+                output.extend(generation.net_object_count.to_le_bytes());
+            }
+            output.extend(self._engine_version.to_le_bytes());
+            output.extend(self._cooker_version.to_le_bytes());
+            output.extend(self._unknown.iter());
+            Ok(())
+        };
+
+        // Offsets are from the start of the file
+        let mut header = Vec::new();
+        write_header([0; 5], &mut header)?;
+        let name_offset = self._header.len() + header.len();
+        let import_offset = name_offset + name_table.len();
+        let export_offset = import_offset + imports.len();
+        let depends_offset = export_offset + exports.len();
+        let data_offset = depends_offset + self._depends.len();
+
+        let mut object_offset = data_offset;
+        for (position, size) in offset_positions.into_iter().zip(object_sizes) {
+            exports[position..position + 4].copy_from_slice(&(object_offset as i32).to_le_bytes());
+            object_offset += size;
+        }
+
+        let mut output = self._header.to_vec();
+        write_header(
+            [name_offset, import_offset, export_offset, depends_offset, data_offset],
+            &mut output,
+        )?;
+        output.extend(name_table);
+        output.extend(imports);
+        output.extend(exports);
+        output.extend(self._depends.iter());
+        output.extend(objects);
+        Ok(output)
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(input: &'a [u8], position: usize) -> Self {
+        Reader { input, position }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = (self.position.checked_add(len))
+            .and_then(|end| self.input.get(self.position..end))
+            .context("Unexpected end of file")?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn array<const LEN: usize>(&mut self) -> Result<[u8; LEN]> {
+        let mut array = [0; LEN];
+        array.copy_from_slice(self.bytes(LEN)?);
+        Ok(array)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn offset(&self, offset: i32) -> Result<usize> {
+        usize::try_from(offset)
+            .ok()
+            .filter(|offset| *offset <= self.input.len())
+            .with_context(|| format!("Invalid offset {offset}"))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = i32::from_le_bytes(self.array()?);
+        self.position -= 4;
+        let byte_len = if len < 0 {
+            len.unsigned_abs() as usize * 2
+        } else {
+            len as usize
+        };
+        Ok(unreal::Deserializer::from_bytes(self.bytes(4 + byte_len)?)?)
+    }
+
+    fn name(&mut self, names: &[NameEntry]) -> Result<String> {
+        let index = self.i32()?;
+        let number = self.i32()?;
+        let name = usize::try_from(index)
+            .ok()
+            .and_then(|index| names.get(index))
+            .with_context(|| format!("Invalid name index {index}"))?;
+        Ok(match number {
+            0 => name.name.clone(),
+            number => format!("{}_{}", name.name, number - 1),
+        })
+    }
+
+    // Sub reader of `len` bytes
+    fn split(&mut self, len: usize) -> Result<Reader<'a>> {
+        Ok(Reader::new(self.bytes(len)?, 0))
+    }
+}
+
+fn read_export(reader: &mut Reader, input: &[u8], names: &[NameEntry]) -> Result<Export> {
+    let class = reader.i32()?;
+    let super_class = reader.i32()?;
+    let outer = reader.i32()?;
+    let name = reader.name(names)?;
+    let archetype = reader.i32()?;
+    let object_flags = reader.u64()?;
+    let size = usize::try_from(reader.i32()?).ok();
+    let offset = reader.i32()?;
+    let offset = reader.offset(offset)?;
+    let component_map = (0..reader.i32()?)
+        .map(|_| Ok((reader.name(names)?, reader.i32()?)))
+        .collect::<Result<_>>()?;
+    let export_flags = reader.u32()?;
+    let net_objects = (0..reader.i32()?).map(|_| reader.i32()).collect::<Result<_>>()?;
+    let package_guid = Dummy(reader.array()?);
+    let package_flags = reader.u32()?;
+
+    // A negative size is as invalid as a size going past the end of the file
+    let data = (size.and_then(|size| offset.checked_add(size)))
+        .and_then(|end| input.get(offset..end))
+        .context("Unexpected end of file")?;
+    Ok(Export {
+        class,
+        _super_class: super_class,
+        outer,
+        name,
+        _archetype: archetype,
+        _object_flags: object_flags,
+        _component_map: component_map,
+        _export_flags: export_flags,
+        _net_objects: net_objects,
+        _package_guid: package_guid,
+        _package_flags: package_flags,
+        data: read_object_data(data, names),
+    })
+}
+
+fn read_object_data(data: &[u8], names: &[NameEntry]) -> ObjectData {
+    let mut reader = Reader::new(data, 0);
+    let properties = reader
+        .i32()
+        .and_then(|net_index| Ok((net_index, read_properties(&mut reader, names)?)));
+    match properties {
+        Ok((net_index, properties)) => ObjectData::Properties {
+            net_index,
+            properties,
+            _remaining: data[reader.position..].into(),
+        },
+        Err(_) => ObjectData::Raw(data.into()),
+    }
+}
+
+fn read_properties(reader: &mut Reader, names: &[NameEntry]) -> Result<Vec<Property>> {
+    let mut properties = Vec::new();
+    loop {
+        let name = reader.name(names)?;
+        if name == NONE {
+            return Ok(properties);
+        }
+        let property_type = reader.name(names)?;
+        let size = usize::try_from(reader.i32()?).context("Invalid property size")?;
+        let array_index = reader.i32()?;
+
+        let value = match property_type.as_str() {
+            "StructProperty" => {
+                let struct_name = reader.name(names)?;
+                let value = read_struct(&struct_name, reader.split(size)?, names);
+                Value::Struct(struct_name, value)
+            }
+            // The value is not counted in the size
+            "BoolProperty" if size == 0 => Value::Bool(reader.u32()? != 0),
+            _ => read_value(property_type, reader.split(size)?, names),
+        };
+
+        properties.push(Property {
+            name,
+            array_index,
+            value,
+        });
+    }
+}
+
+// Falls back to `Value::Unknown` when the payload does not match the type
+fn read_value(property_type: String, mut reader: Reader, names: &[NameEntry]) -> Value {
+    let len = reader.input.len();
+    let value = match (property_type.as_str(), len) {
+        ("IntProperty", 4) => reader.i32().map(Value::Int),
+        ("FloatProperty", 4) => reader.f32().map(Value::Float),
+        ("ByteProperty", 1) => reader.array().map(|[byte]| Value::Byte(byte)),
+        ("ByteProperty", 8) => reader.name(names).map(Value::Enum),
+        ("NameProperty", 8) => reader.name(names).map(Value::Name),
+        ("ObjectProperty", 4) => reader.i32().map(Value::Object),
+        ("StringRefProperty", 4) => reader.i32().map(Value::StringRef),
+        ("StrProperty", _) => reader.string().map(Value::Str),
+        ("ArrayProperty", _) => reader
+            .i32()
+            .and_then(|count| read_array(count, &mut reader, names).map(Value::Array)),
+        _ => Err(anyhow::anyhow!("Unknown property")),
+    };
+
+    match value {
+        Ok(value) if reader.is_empty() => value,
+        _ => Value::Unknown(property_type, reader.input.into()),
+    }
+}
+
+fn read_struct(struct_name: &str, mut reader: Reader, names: &[NameEntry]) -> StructValue {
+    let value = match (struct_name, reader.input.len()) {
+        ("Vector", 12) => read_vector(&mut reader).map(StructValue::Vector),
+        ("Rotator", 12) => (|| {
+            let (pitch, yaw, roll) = (reader.i32()?, reader.i32()?, reader.i32()?);
+            Ok(StructValue::Rotator(Rotator { pitch, yaw, roll }))
+        })(),
+        ("LinearColor", 16) => (|| {
+            let (r, g, b, a) = (reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?);
+            Ok(StructValue::LinearColor(LinearColor { r, g, b, a }))
+        })(),
+        _ => read_properties(&mut reader, names).map(StructValue::Properties),
+    };
+
+    match value {
+        Ok(value) if reader.is_empty() => value,
+        _ => StructValue::Raw(reader.input.into()),
+    }
+}
+
+fn read_vector(reader: &mut Reader) -> Result<Vector3D> {
+    Ok(Vector3D {
+        x: reader.f32()?,
+        y: reader.f32()?,
+        z: reader.f32()?,
+    })
+}
+
+// The first element type that reads the whole array
+fn read_array(count: i32, reader: &mut Reader, names: &[NameEntry]) -> Result<ArrayValue> {
+    let count = usize::try_from(count).context("Invalid array length")?;
+    let input = reader.input;
+    let data = &input[reader.position..];
+
+    let structs = |reader: &mut Reader| -> Result<_> {
+        let structs = (0..count)
+            .map(|_| read_properties(reader, names))
+            .collect::<Result<_>>()?;
+        Ok(ArrayValue::Structs(structs))
+    };
+    let ints = |reader: &mut Reader| -> Result<_> {
+        ensure!(data.len() == count * 4, "Not an array of ints");
+        Ok(ArrayValue::Ints(
+            (0..count).map(|_| reader.i32()).collect::<Result<_>>()?,
+        ))
+    };
+    let strings = |reader: &mut Reader| -> Result<_> {
+        Ok(ArrayValue::Strings(
+            (0..count).map(|_| reader.string()).collect::<Result<_>>()?,
+        ))
+    };
+    let vectors = |reader: &mut Reader| -> Result<_> {
+        ensure!(data.len() == count * 12, "Not an array of vectors");
+        Ok(ArrayValue::Vectors(
+            (0..count).map(|_| read_vector(reader)).collect::<Result<_>>()?,
+        ))
+    };
+
+    let read_all = |read: &dyn Fn(&mut Reader) -> Result<ArrayValue>| {
+        let mut array_reader = Reader::new(data, 0);
+        read(&mut array_reader).ok().filter(|_| array_reader.is_empty())
+    };
+    let array = read_all(&structs)
+        .or_else(|| read_all(&ints))
+        .or_else(|| read_all(&strings))
+        .or_else(|| read_all(&vectors))
+        .unwrap_or_else(|| ArrayValue::Raw(count as i32, data.into()));
+
+    reader.position = reader.input.len();
+    Ok(array)
+}
+
+// Indices of the names, new names are added at the end of the table
+struct NameTable {
+    entries: Vec<NameEntry>,
+    indices: HashMap<String, i32>,
+}
+
+impl NameTable {
+    fn new(entries: &[NameEntry]) -> Self {
+        let mut indices = HashMap::new();
+        for (idx, entry) in entries.iter().enumerate() {
+            indices.entry(entry.name.clone()).or_insert(idx as i32);
+        }
+        NameTable {
+            entries: entries.to_vec(),
+            indices,
+        }
+    }
+
+    fn insert(&mut self, name: &str) -> i32 {
+        if let Some(idx) = self.indices.get(name) {
+            return *idx;
+        }
+        let flags = self.entries.first().map(|entry| entry.flags).unwrap_or_default();
+        self.entries.push(NameEntry {
+            name: name.to_owned(),
+            flags,
+        });
+        let idx = self.entries.len() as i32 - 1;
+        self.indices.insert(name.to_owned(), idx);
+        idx
+    }
+
+    // `Name_N` is `Name` with the number N + 1 unless it is in the table, like Unreal a number
+    // with a leading zero is part of the name
+    fn index(&mut self, name: &str) -> (i32, i32) {
+        if let Some(idx) = self.indices.get(name) {
+            return (*idx, 0);
+        }
+        if let Some((base, number)) = name.rsplit_once('_') {
+            if let Ok(parsed) = number.parse::<i32>() {
+                if (0..i32::MAX).contains(&parsed) && parsed.to_string() == number {
+                    return (self.insert(base), parsed + 1);
+                }
+            }
+        }
+        (self.insert(name), 0)
+    }
+}
+
+fn write_string(string: &str, output: &mut Vec<u8>) -> Result<()> {
+    output.extend(unreal::Serializer::to_vec(&string)?);
+    Ok(())
+}
+
+fn write_name(name: &str, names: &mut NameTable, output: &mut Vec<u8>) {
+    let (index, number) = names.index(name);
+    output.extend(index.to_le_bytes());
+    output.extend(number.to_le_bytes());
+}
+
+fn write_object_data(data: &ObjectData, names: &mut NameTable, output: &mut Vec<u8>) -> Result<()> {
+    match data {
+        ObjectData::Properties {
+            net_index,
+            properties,
+            _remaining,
+        } => {
+            output.extend(net_index.to_le_bytes());
+            write_properties(properties, names, output)?;
+            output.extend(_remaining.iter());
+        }
+        ObjectData::Raw(data) => output.extend(data.iter()),
+    }
+    Ok(())
+}
+
+fn write_properties(properties: &[Property], names: &mut NameTable, output: &mut Vec<u8>) -> Result<()> {
+    for Property {
+        name,
+        array_index,
+        value,
+    } in properties
+    {
+        let mut payload = Vec::new();
+        let property_type = match value {
+            Value::Int(value) => {
+                payload.extend(value.to_le_bytes());
+                "IntProperty"
+            }
+            Value::Float(value) => {
+                payload.extend(value.to_le_bytes());
+                "FloatProperty"
+            }
+            Value::Bool(_) => "BoolProperty",
+            Value::Byte(value) => {
+                payload.push(*value);
+                "ByteProperty"
+            }
+            Value::Enum(value) => {
+                write_name(value, names, &mut payload);
+                "ByteProperty"
+            }
+            Value::Name(value) => {
+                write_name(value, names, &mut payload);
+                "NameProperty"
+            }
+            Value::Object(value) => {
+                payload.extend(value.to_le_bytes());
+                "ObjectProperty"
+            }
+            Value::Str(value) => {
+                write_string(value, &mut payload)?;
+                "StrProperty"
+            }
+            Value::StringRef(value) => {
+                payload.extend(value.to_le_bytes());
+                "StringRefProperty"
+            }
+            Value::Struct(_, value) => {
+                write_struct(value, names, &mut payload)?;
+                "StructProperty"
+            }
+            Value::Array(value) => {
+                write_array(value, names, &mut payload)?;
+                "ArrayProperty"
+            }
+            Value::Unknown(property_type, value) => {
+                payload.extend(value.iter());
+                property_type
+            }
+        };
+
+        write_name(name, names, output);
+        write_name(property_type, names, output);
+        output.extend((payload.len() as i32).to_le_bytes());
+        output.extend(array_index.to_le_bytes());
+        match value {
+            Value::Struct(struct_name, _) => write_name(struct_name, names, output),
+            Value::Bool(value) => output.extend((*value as u32).to_le_bytes()),
+            _ => (),
+        }
+        output.extend(payload);
+    }
+    write_name(NONE, names, output);
+    Ok(())
+}
+
+fn write_vector(Vector3D { x, y, z }: &Vector3D, output: &mut Vec<u8>) {
+    output.extend([x, y, z].into_iter().flat_map(|coord| coord.to_le_bytes()));
+}
+
+fn write_struct(value: &StructValue, names: &mut NameTable, output: &mut Vec<u8>) -> Result<()> {
+    match value {
+        StructValue::Vector(vector) => write_vector(vector, output),
+        StructValue::Rotator(Rotator { pitch, yaw, roll }) => {
+            output.extend([pitch, yaw, roll].into_iter().flat_map(|value| value.to_le_bytes()))
+        }
+        StructValue::LinearColor(LinearColor { r, g, b, a }) => {
+            output.extend([r, g, b, a].into_iter().flat_map(|value| value.to_le_bytes()))
+        }
+        StructValue::Properties(properties) => write_properties(properties, names, output)?,
+        StructValue::Raw(data) => output.extend(data.iter()),
+    }
+    Ok(())
+}
+
+fn write_array(value: &ArrayValue, names: &mut NameTable, output: &mut Vec<u8>) -> Result<()> {
+    let count = match value {
+        ArrayValue::Structs(structs) => structs.len(),
+        ArrayValue::Ints(ints) => ints.len(),
+        ArrayValue::Strings(strings) => strings.len(),
+        ArrayValue::Vectors(vectors) => vectors.len(),
+        ArrayValue::Raw(count, _) => *count as usize,
+    };
+    output.extend((count as i32).to_le_bytes());
+
+    match value {
+        ArrayValue::Structs(structs) => {
+            for properties in structs {
+                write_properties(properties, names, output)?;
+            }
+        }
+        ArrayValue::Ints(ints) => output.extend(ints.iter().flat_map(|value| value.to_le_bytes())),
+        ArrayValue::Strings(strings) => {
+            for string in strings {
+                write_string(string, output)?;
+            }
+        }
+        ArrayValue::Vectors(vectors) => {
+            for vector in vectors {
+                write_vector(vector, output);
+            }
+        }
+        ArrayValue::Raw(_, data) => output.extend(data.iter()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        io::{Cursor, Read},
+    };
+
+    use anyhow::Result;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    fn player() -> Result<Vec<u8>> {
+        let input = fs::read("test/ME1Save.MassEffectSave")?;
+        // Magic number, version, zip offset
+        let zip_offset = u32::from_le_bytes(input[8..12].try_into()?) as usize;
+        let mut zip = ZipArchive::new(Cursor::new(&input[zip_offset..]))?;
+        let mut player = Vec::new();
+        zip.by_name("player.sav")?.read_to_end(&mut player)?;
+        Ok(player)
+    }
+
+    #[test]
+    fn deserialize_serialize() -> Result<()> {
+        let input = player()?;
+        let package = Package::from_bytes(&input)?;
+        assert!(package
+            .exports
+            .iter()
+            .all(|export| matches!(export.data, ObjectData::Properties { .. })));
+
+        let output = package.to_bytes()?;
+        assert!(input == output);
+
+        // RON
+        let package: Package = ron::from_str(&ron::to_string(&package)?)?;
+        assert!(package.to_bytes()? == output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_export_size() -> Result<()> {
+        let mut input = player()?;
+        let package_offset = u32::from_le_bytes(input[8..12].try_into()?) as usize;
+        let mut reader = Reader::new(&input, package_offset + 12);
+        let _folder_name = reader.string()?;
+        reader.bytes(16)?;
+        let export_offset = reader.i32()? as usize;
+
+        // The size of the first export
+        let size = export_offset + 32;
+        for invalid_size in [-1, i32::MAX] {
+            input[size..size + 4].copy_from_slice(&invalid_size.to_le_bytes());
+            let err = Package::from_bytes(&input).err().context("Malformed export size accepted")?;
+            assert_eq!(err.root_cause().to_string(), "Unexpected end of file");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_names_and_imports() -> Result<()> {
+        let input = player()?;
+        let mut package = Package::from_bytes(&input)?;
+        let import_count = package.imports.len();
+
+        let hair = package.find_or_add_import("BIOG_HMF_HIR_PRO.Test.HMF_HIR_Test_MDL", "Engine", "SkeletalMesh");
+        assert_eq!(package.imports.len(), import_count + 2);
+        assert_eq!(
+            package.import_path(hair).as_deref(),
+            Some("BIOG_HMF_HIR_PRO.Test.HMF_HIR_Test_MDL")
+        );
+        assert_eq!(
+            package.find_or_add_import("BIOG_HMF_HIR_PRO.Test.HMF_HIR_Test_MDL", "Engine", "SkeletalMesh"),
+            hair
+        );
+
+        let save_game = package.export_of_class("BioSaveGame").context("No BioSaveGame")?;
+        let properties = package.export_mut(save_game).unwrap().properties_mut();
+        properties[0].name = "m_TestProperty_12".to_owned();
+
+        let package = Package::from_bytes(&package.to_bytes()?)?;
+        assert_eq!(package.imports.len(), import_count + 2);
+        assert_eq!(
+            package.import_path(hair).as_deref(),
+            Some("BIOG_HMF_HIR_PRO.Test.HMF_HIR_Test_MDL")
+        );
+        assert_eq!(
+            package.export(save_game).unwrap().properties()[0].name,
+            "m_TestProperty_12"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,484 @@
+use anyhow::{bail, ensure, Context, Result};
+
+use super::package::{find, find_mut, ArrayValue, Package, Property, StructValue, Value};
+use crate::{
+    save_data::shared::{
+        appearance::{HeadMorph, LinearColor},
+        player::{Notoriety, Origin},
+        Vector3D,
+    },
+    IndexMap,
+};
+
+const DIFFICULTIES: [&str; 5] = ["Casual", "Normal", "Veteran", "Hardcore", "Insanity"];
+const LODS: [&str; 3] = ["m_aVertexBuffersLOD0", "m_aVertexBuffersLOD1", "m_aVertexBuffersLOD2"];
+
+// Combat difficulty of ME1 and ME1LE, the index itself if it is unknown
+pub fn difficulty_name(difficulty: i32) -> String {
+    usize::try_from(difficulty)
+        .ok()
+        .and_then(|difficulty| DIFFICULTIES.get(difficulty))
+        .map_or_else(|| difficulty.to_string(), |difficulty| difficulty.to_string())
+}
+
+fn int(properties: &[Property], name: &str) -> Option<i32> {
+    match find(properties, name)? {
+        Value::Int(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn float_property(properties: &[Property], name: &str) -> Option<f32> {
+    float(find(properties, name)?)
+}
+
+// The value of an enum without its prefix, e.g. `ADEPT` for `BIO_PARTY_MEMBER_CLASS_BASE_ADEPT`
+fn enum_value<'a>(properties: &'a [Property], name: &str, prefix: &str) -> Option<&'a str> {
+    match find(properties, name)? {
+        Value::Enum(value) => value.strip_prefix(prefix),
+        _ => None,
+    }
+}
+
+fn object(properties: &[Property], name: &str) -> Option<i32> {
+    match find(properties, name)? {
+        Value::Object(index) => Some(*index),
+        _ => None,
+    }
+}
+
+fn structs<'a>(properties: &'a [Property], name: &str) -> Option<&'a [Vec<Property>]> {
+    match find(properties, name)? {
+        Value::Array(ArrayValue::Structs(structs)) => Some(structs),
+        _ => None,
+    }
+}
+
+// Name and value of the elements of an array of structs, e.g. `{ nName, sValue }`
+fn named<'a, T>(
+    properties: &'a [Property],
+    array: &str,
+    (key, value): (&str, &str),
+    map: impl Fn(&'a Value) -> Option<T>,
+) -> Option<IndexMap<String, T>> {
+    structs(properties, array)?
+        .iter()
+        .map(|element| match find(element, key)? {
+            Value::Name(name) => Some((name.clone(), map(find(element, value)?)?)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn set_named(properties: &mut [Property], array: &str, (key, value): (&str, &str), values: Vec<(String, Value)>) {
+    if let Some(array) = find_mut(properties, array) {
+        let structs = values
+            .into_iter()
+            .map(|(name, element)| {
+                vec![
+                    Property {
+                        name: key.to_owned(),
+                        array_index: 0,
+                        value: Value::Name(name),
+                    },
+                    Property {
+                        name: value.to_owned(),
+                        array_index: 0,
+                        value: element,
+                    },
+                ]
+            })
+            .collect();
+        *array = Value::Array(ArrayValue::Structs(structs));
+    }
+}
+
+fn entries<T: Copy>(map: &IndexMap<String, T>, value: impl Fn(T) -> Value) -> Vec<(String, Value)> {
+    map.iter()
+        .map(|(name, element)| (name.clone(), value(*element)))
+        .collect()
+}
+
+fn float(value: &Value) -> Option<f32> {
+    match value {
+        Value::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+fn vector(value: &Value) -> Option<Vector3D> {
+    match value {
+        Value::Struct(_, StructValue::Vector(vector)) => Some(*vector),
+        _ => None,
+    }
+}
+
+fn linear_color(value: &Value) -> Option<LinearColor> {
+    match value {
+        Value::Struct(_, StructValue::LinearColor(color)) => Some(*color),
+        _ => None,
+    }
+}
+
+fn vertices<'a>(properties: &'a [Property], lod: &str) -> Option<&'a Vec<Vector3D>> {
+    match find(properties, lod)? {
+        Value::Struct(_, StructValue::Properties(buffer)) => match find(buffer, "m_vPosition")? {
+            Value::Array(ArrayValue::Vectors(vertices)) => Some(vertices),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn vertices_mut<'a>(properties: &'a mut [Property], lod: &str) -> Option<&'a mut Vec<Vector3D>> {
+    match find_mut(properties, lod)? {
+        Value::Struct(_, StructValue::Properties(buffer)) => match find_mut(buffer, "m_vPosition")? {
+            Value::Array(ArrayValue::Vectors(vertices)) => Some(vertices),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The player, the squad and the head morph of player.sav, starting from the `BioSaveGame` object
+impl Package {
+    fn properties(&self, index: i32) -> Option<&[Property]> {
+        self.export(index).map(|export| export.properties())
+    }
+
+    fn save_game(&self) -> Option<&[Property]> {
+        self.properties(self.export_of_class("BioSaveGame")?)
+    }
+
+    fn player(&self) -> Option<&[Property]> {
+        self.properties(object(self.save_game()?, "m_Player")?)
+    }
+
+    pub fn player_name(&self) -> Option<&str> {
+        match find(self.player()?, "m_FirstName")? {
+            Value::Str(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn level(&self) -> Option<i32> {
+        int(self.player()?, "m_XPLevel")
+    }
+
+    fn squad_object(&self) -> Option<&[Property]> {
+        self.properties(object(self.player()?, "m_Squad")?)
+    }
+
+    pub fn credits(&self) -> Option<i32> {
+        let inventory = self.properties(object(self.squad_object()?, "m_Inventory")?)?;
+        int(inventory, "m_nResourceCredits")
+    }
+
+    // e.g. `Adept`
+    pub fn class(&self) -> Option<String> {
+        let class = enum_value(self.player()?, "m_ClassBase", "BIO_PARTY_MEMBER_CLASS_BASE_")?;
+        let mut chars = class.chars();
+        let first = chars.next()?;
+        Some(first.to_string() + &chars.as_str().to_lowercase())
+    }
+
+    // The experience is shared by the squad
+    pub fn current_xp(&self) -> Option<f32> {
+        int(self.squad_object()?, "m_nSquadExperience").map(|xp| xp as f32)
+    }
+
+    pub fn origin(&self) -> Option<Origin> {
+        let origin = match enum_value(self.player()?, "m_BackgroundOrigin", "BIO_PLAYER_CHARACTER_BACKGROUND_ORIGIN_")? {
+            "NONE" => Origin::None,
+            "SPACER" => Origin::Spacer,
+            "COLONY" => Origin::Colonist,
+            "EARTHBORN" => Origin::Earthborn,
+            _ => return None,
+        };
+        Some(origin)
+    }
+
+    pub fn notoriety(&self) -> Option<Notoriety> {
+        let notoriety = match enum_value(
+            self.player()?,
+            "m_BackgroundNotoriety",
+            "BIO_PLAYER_CHARACTER_BACKGROUND_NOTORIETY_",
+        )? {
+            "NONE" => Notoriety::None,
+            "SURVIVOR" => Notoriety::Survivor,
+            "WARHERO" => Notoriety::Warhero,
+            "RUTHLESS" => Notoriety::Ruthless,
+            _ => return None,
+        };
+        Some(notoriety)
+    }
+
+    pub fn seconds_played(&self) -> Option<f32> {
+        float_property(self.save_game()?, "m_fPlaythroughTime")
+    }
+
+    pub fn is_female(&self) -> Option<bool> {
+        match find(self.player()?, "m_Gender")? {
+            Value::Enum(gender) => Some(gender == "BIO_ATTRIBUTE_PAWN_GENDER_FEMALE"),
+            _ => None,
+        }
+    }
+
+    pub fn difficulty(&self) -> Option<String> {
+        match find(self.save_game()?, "m_GameOptions")? {
+            Value::Struct(_, StructValue::Properties(options)) => {
+                int(options, "m_nCombatDifficulty").map(difficulty_name)
+            }
+            _ => None,
+        }
+    }
+
+    // Henchmen tags
+    pub fn squad(&self) -> Vec<&str> {
+        let henchmen = self.save_game().and_then(|save_game| structs(save_game, "m_Henchmen"));
+        henchmen
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|henchman| match find(henchman, "Tag")? {
+                Value::Name(tag) => Some(tag.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // `BioMorphFaceSaveObject`, 0 for the default Shepard
+    fn morph_face(&self) -> Option<i32> {
+        let appearance = self.properties(object(self.save_game()?, "m_PlayerAppearance")?)?;
+        object(appearance, "m_oMorphFace")
+    }
+
+    fn object_path(&self, index: i32) -> Option<String> {
+        match index {
+            0 => Some("None".to_owned()),
+            index => self.import_path(index),
+        }
+    }
+
+    // ME1 has no accessory mesh and no lod3, `None` if the save does not have the expected objects
+    pub fn head_morph(&self) -> Option<Option<HeadMorph>> {
+        let morph_face = match self.morph_face()? {
+            0 => return Some(None),
+            morph_face => self.properties(morph_face)?,
+        };
+        let material_overrides = self.properties(object(morph_face, "m_oMaterialOverrides")?)?;
+
+        let lod = |lod| vertices(morph_face, lod).cloned();
+        Some(Some(HeadMorph {
+            hair_mesh: self.object_path(object(morph_face, "m_oHairMesh")?)?,
+            accessory_mesh: Vec::new(),
+            morph_features: named(morph_face, "m_aMorphFeatures", ("sFeatureName", "Offset"), float)?,
+            offset_bones: named(morph_face, "m_aFinalSkeleton", ("nName", "vPos"), vector)?,
+            lod0_vertices: lod(LODS[0])?,
+            lod1_vertices: lod(LODS[1])?,
+            lod2_vertices: lod(LODS[2])?,
+            lod3_vertices: Vec::new(),
+            scalar_parameters: named(material_overrides, "m_aScalarOverrides", ("nName", "sValue"), float)?,
+            vector_parameters: named(
+                material_overrides,
+                "m_aColorOverrides",
+                ("nName", "cValue"),
+                linear_color,
+            )?,
+            texture_parameters: named(
+                material_overrides,
+                "m_aTextureOverrides",
+                ("nName", "m_pTexture"),
+                |value| match value {
+                    Value::Object(texture) => self.object_path(*texture),
+                    _ => None,
+                },
+            )?,
+        }))
+    }
+
+    // Only an existing head morph can be replaced, the vertex counts are those of the ME1 head : lod0
+    // must match, lod1 and lod2 are left untouched if they do not (ME1LE heads have empty LODs)
+    pub fn set_head_morph(&mut self, head_morph: &Option<HeadMorph>) -> Result<()> {
+        let appearance = self
+            .save_game()
+            .and_then(|save_game| object(save_game, "m_PlayerAppearance"))
+            .context("No player appearance in the save")?;
+        let morph_face = self.morph_face().context("No morph face in the save")?;
+
+        let head_morph = match head_morph {
+            Some(head_morph) => head_morph,
+            None => {
+                let appearance = self
+                    .export_mut(appearance)
+                    .context("No player appearance in the save")?;
+                if let Some(morph_face) = find_mut(appearance.properties_mut(), "m_oMorphFace") {
+                    *morph_face = Value::Object(0);
+                }
+                return Ok(());
+            }
+        };
+
+        if morph_face == 0 {
+            bail!("The default Shepard of an ME1 save has no head morph to replace, create one in the game first");
+        }
+        ensure!(
+            head_morph.accessory_mesh.is_empty(),
+            "ME1 head morphs have no accessory mesh"
+        );
+        let material_overrides = self
+            .properties(morph_face)
+            .and_then(|morph_face| object(morph_face, "m_oMaterialOverrides"))
+            .context("No material overrides in the save")?;
+
+        let mut object_import = |path: &str, class: &str| match path {
+            "None" => 0,
+            path => self.find_or_add_import(path, "Engine", class),
+        };
+        let hair_mesh = object_import(&head_morph.hair_mesh, "SkeletalMesh");
+        let textures = head_morph
+            .texture_parameters
+            .iter()
+            .map(|(name, texture)| (name.clone(), Value::Object(object_import(texture, "Texture2D"))))
+            .collect();
+
+        let face = self.export_mut(morph_face).context("No morph face in the save")?;
+        let face = face.properties_mut();
+        let lod0_len = vertices(face, LODS[0]).map(Vec::len).context("No lod0 in the save")?;
+        ensure!(
+            head_morph.lod0_vertices.len() == lod0_len,
+            "lod0 has {} vertices, ME1 heads have {lod0_len}",
+            head_morph.lod0_vertices.len()
+        );
+
+        let lods = [
+            &head_morph.lod0_vertices,
+            &head_morph.lod1_vertices,
+            &head_morph.lod2_vertices,
+        ];
+        // A LOD whose vertex count differs from the save's is left as it is, its vertices
+        // would not match the mesh
+        for (lod, head_morph_vertices) in LODS.into_iter().zip(lods) {
+            if let Some(vertices) = vertices_mut(face, lod) {
+                if head_morph_vertices.len() == vertices.len() {
+                    vertices.clone_from(head_morph_vertices);
+                }
+            }
+        }
+
+        if let Some(value) = find_mut(face, "m_oHairMesh") {
+            *value = Value::Object(hair_mesh);
+        }
+        let vector_value = |vector| Value::Struct("Vector".to_owned(), StructValue::Vector(vector));
+        let color_value = |color| Value::Struct("LinearColor".to_owned(), StructValue::LinearColor(color));
+        set_named(
+            face,
+            "m_aMorphFeatures",
+            ("sFeatureName", "Offset"),
+            entries(&head_morph.morph_features, Value::Float),
+        );
+        set_named(
+            face,
+            "m_aFinalSkeleton",
+            ("nName", "vPos"),
+            entries(&head_morph.offset_bones, vector_value),
+        );
+
+        let overrides = self
+            .export_mut(material_overrides)
+            .context("No material overrides in the save")?
+            .properties_mut();
+        set_named(overrides, "m_aTextureOverrides", ("nName", "m_pTexture"), textures);
+        set_named(
+            overrides,
+            "m_aColorOverrides",
+            ("nName", "cValue"),
+            entries(&head_morph.vector_parameters, color_value),
+        );
+        set_named(
+            overrides,
+            "m_aScalarOverrides",
+            ("nName", "sValue"),
+            entries(&head_morph.scalar_parameters, Value::Float),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{borrow::Cow, fs};
+
+    use super::*;
+    use crate::save_data::mass_effect_1::Me1SaveGame;
+    use crate::{
+        save_game::{SaveGame, SaveKind},
+        unreal,
+    };
+
+    fn me1() -> Result<Me1SaveGame> {
+        Ok(unreal::Deserializer::from_bytes(&fs::read(
+            "test/ME1Save.MassEffectSave",
+        )?)?)
+    }
+
+    #[test]
+    fn player() -> Result<()> {
+        let me1 = me1()?;
+        assert_eq!(me1.player_name(), Some("Clare"));
+        assert_eq!(me1.level(), Some(60));
+        assert_eq!(me1.credits(), Some(9999999));
+        assert_eq!(me1.is_female(), Some(true));
+        assert_eq!(me1.difficulty().as_deref(), Some("Insanity"));
+        assert_eq!(me1.player.class().as_deref(), Some("Adept"));
+        assert_eq!(me1.player.current_xp(), Some(862100.0));
+        assert!(matches!(me1.player.origin(), Some(Origin::Colonist)));
+        assert!(matches!(me1.player.notoriety(), Some(Notoriety::Ruthless)));
+        assert!(me1.player.seconds_played().is_some());
+        assert_eq!(me1.squad().len(), 12);
+        assert_eq!(me1.squad()[..2], ["hench_jenkins", "hench_humanmale"]);
+        Ok(())
+    }
+
+    #[test]
+    fn head_morph() -> Result<()> {
+        let mut package = me1()?.player;
+        let expected = package.to_bytes()?;
+        let mut head_morph = package.head_morph().flatten().context("No head morph")?;
+        assert_eq!(head_morph.hair_mesh, "BIOG_HMF_HIR_PRO.Classy.HMF_HIR_Cls_MDL");
+        assert_eq!(head_morph.morph_features.len(), 98);
+        assert_eq!(head_morph.offset_bones.len(), 33);
+        assert_eq!(head_morph.lod0_vertices.len(), 2232);
+        assert_eq!(head_morph.texture_parameters["HED_Scalp_SpecShift"], "None");
+
+        // Unchanged
+        package.set_head_morph(&Some(head_morph.clone()))?;
+        assert!(package.to_bytes()? == expected);
+
+        // ME1LE head morph, its LODs of another vertex count are left untouched
+        let me1_le = SaveKind::deserialize(fs::read("test/ME1LeSave.pcsav")?)?;
+        let me1_le = me1_le.head_morph().and_then(Cow::into_owned).context("No ME1LE head morph")?;
+        assert_eq!(me1_le.lod0_vertices.len(), head_morph.lod0_vertices.len());
+        assert_ne!(me1_le.lod1_vertices.len(), head_morph.lod1_vertices.len());
+        package.set_head_morph(&Some(me1_le.clone()))?;
+        let imported = package.head_morph().flatten().context("No head morph")?;
+        assert!(imported.lod0_vertices == me1_le.lod0_vertices && imported.lod1_vertices == head_morph.lod1_vertices);
+
+        head_morph.lod0_vertices[0].x += 1.0;
+        head_morph.hair_mesh = "BIOG_HMF_HIR_PRO.Test.HMF_HIR_Test_MDL".to_owned();
+        head_morph.texture_parameters["HED_Scalp_SpecShift"] =
+            "BIOG_HMF_HIR_PRO.Human.HMF_HIR_PROAll_SpecShift".to_owned();
+        head_morph.morph_features.insert("test_feature".to_owned(), 0.5);
+        package.set_head_morph(&Some(head_morph.clone()))?;
+        let package = Package::from_bytes(&package.to_bytes()?)?;
+        assert!(package.head_morph() == Some(Some(head_morph.clone())));
+
+        let mut wrong = head_morph.clone();
+        wrong.lod0_vertices.pop();
+        assert!(package.clone().set_head_morph(&Some(wrong)).is_err());
+        let mut wrong = head_morph;
+        wrong.accessory_mesh.push("Test.Accessory".to_owned());
+        assert!(package.clone().set_head_morph(&Some(wrong)).is_err());
+
+        Ok(())
+    }
+}
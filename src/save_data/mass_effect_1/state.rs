@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+use crate::save_data::{
+    shared::{plot::PlotTable, Rotator, Vector3D},
+    Dummy, List,
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct State {
+    _begin: Dummy<12>,
+    pub base_level_name: String,
+    location: Vector3D,
+    rotation: Rotator,
+    pub plot: PlotTable,
+    _remaining: List<u8>, // Journal, codex, etc. not mapped yet
+}
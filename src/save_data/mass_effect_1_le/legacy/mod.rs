@@ -4,18 +4,54 @@ mod pawn;
 
 use self::{art_placeable::*, inventory::*, pawn::*};
 
-use std::fmt;
+use std::{cell::RefCell, fmt};
 
 use serde::{de, Deserialize, Deserializer, Serialize};
 
 use crate::{
     save_data::{
+        next_element,
         shared::{Rotator, Vector3D},
-        Dummy,
+        Dummy, List,
     },
     IndexMap,
 };
 
+// Byte size, after the owner, of the objects of classes unknown to the editor (DLC, mods)
+// Legacy objects are not length-prefixed, an object of unknown class can only be kept as raw
+// bytes if the size of its class is given, see `SaveKind::deserialize_with`
+#[derive(Clone, Default)]
+pub struct ClassSizes(IndexMap<String, usize>);
+
+impl ClassSizes {
+    pub fn insert(&mut self, class_name: impl Into<String>, size: usize) {
+        self.0.insert(class_name.into(), size);
+    }
+
+    // `Deserialize` takes no argument, the sizes are only visible to the `BaseObject`s
+    // deserialized by `f`, on the current thread
+    pub(crate) fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        let outer = CLASS_SIZES.with(|class_sizes| class_sizes.replace(self.clone()));
+        let result = f();
+        CLASS_SIZES.with(|class_sizes| class_sizes.replace(outer));
+        result
+    }
+}
+
+impl FromIterator<(String, usize)> for ClassSizes {
+    fn from_iter<I: IntoIterator<Item = (String, usize)>>(iter: I) -> Self {
+        ClassSizes(iter.into_iter().collect())
+    }
+}
+
+thread_local! {
+    static CLASS_SIZES: RefCell<ClassSizes> = RefCell::default();
+}
+
+fn class_size(class_name: &str) -> Option<usize> {
+    CLASS_SIZES.with(|class_sizes| class_sizes.borrow().0.get(class_name).copied())
+}
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct Map {
     levels: IndexMap<String, Level>,
@@ -41,6 +77,47 @@ impl<'de> Deserialize<'de> for BaseObject {
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            // The object variant is explicit, no need to dispatch on the class name
+            #[derive(Deserialize)]
+            #[serde(rename = "BaseObject")]
+            struct HumanReadable {
+                _class_name: String,
+                owner_name: String,
+                owner_class: Option<String>,
+                _object: Object,
+            }
+
+            let HumanReadable {
+                _class_name,
+                owner_name,
+                owner_class,
+                _object,
+            } = Deserialize::deserialize(deserializer)?;
+
+            return Ok(BaseObject {
+                _class_name,
+                owner_name,
+                owner_class,
+                _object,
+            });
+        }
+
+        const CLASS_NAMES: &[&str] = &[
+            "BioPawnBehaviorSaveObject",
+            "BioPawnSaveObject",
+            "BioBaseSquadSaveObject",
+            "BioShopSaveObject",
+            "BioInventorySaveObject",
+            "BioItemXModdableSaveObject",
+            "BioItemXModSaveObject",
+            "BioArtPlaceableBehaviorSaveObject",
+            "BioArtPlaceableSaveObject",
+            "BioVehicleBehaviorSaveObject",
+            "BioVehicleSaveObject",
+            "BioWorldInfoSaveObject",
+        ];
+
         struct BaseObjectVisitor;
         impl<'de> de::Visitor<'de> for BaseObjectVisitor {
             type Value = BaseObject;
@@ -53,23 +130,46 @@ impl<'de> Deserialize<'de> for BaseObject {
             where
                 A: de::SeqAccess<'de>,
             {
-                let class_name: String = seq.next_element()?.unwrap();
-                let owner_name = seq.next_element()?.unwrap();
-                let owner_class = seq.next_element()?.unwrap();
+                let mut len = 0;
+                let class_name: String = next_element(&mut seq, &mut len, &self)?;
+                let unknown_size = match CLASS_NAMES.contains(&class_name.as_str()) {
+                    true => None,
+                    false => match class_size(&class_name) {
+                        Some(size) => Some(size),
+                        None => return Err(de::Error::unknown_variant(&class_name, CLASS_NAMES)),
+                    },
+                };
+
+                let owner_name = next_element(&mut seq, &mut len, &self)?;
+                let owner_class = next_element(&mut seq, &mut len, &self)?;
+                if let Some(size) = unknown_size {
+                    let raw = seq
+                        .next_element_seed(UnknownObject(size))?
+                        .ok_or_else(|| de::Error::invalid_length(len, &self))?;
+                    return Ok(BaseObject {
+                        _class_name: class_name,
+                        owner_name,
+                        owner_class,
+                        _object: Object::Unknown(raw),
+                    });
+                }
+
                 let object = match class_name.as_str() {
-                    "BioPawnBehaviorSaveObject" => Object::PawnBehavior(seq.next_element()?.unwrap()),
-                    "BioPawnSaveObject" => Object::Pawn(seq.next_element()?.unwrap()),
-                    "BioBaseSquadSaveObject" => Object::BaseSquad(seq.next_element()?.unwrap()),
-                    "BioShopSaveObject" => Object::Shop(seq.next_element()?.unwrap()),
-                    "BioInventorySaveObject" => Object::Inventory(seq.next_element()?.unwrap()),
-                    "BioItemXModdableSaveObject" => Object::Item(seq.next_element()?.unwrap()),
-                    "BioItemXModSaveObject" => Object::ItemMod(seq.next_element()?.unwrap()),
-                    "BioArtPlaceableBehaviorSaveObject" => Object::ArtPlaceableBehavior(seq.next_element()?.unwrap()),
-                    "BioArtPlaceableSaveObject" => Object::ArtPlaceable(seq.next_element()?.unwrap()),
-                    "BioVehicleBehaviorSaveObject" => Object::VehicleBehavior(seq.next_element()?.unwrap()),
-                    "BioVehicleSaveObject" => Object::Vehicle(seq.next_element()?.unwrap()),
-                    "BioWorldInfoSaveObject" => Object::World(seq.next_element()?.unwrap()),
-                    _ => unreachable!(),
+                    "BioPawnBehaviorSaveObject" => Object::PawnBehavior(next_element(&mut seq, &mut len, &self)?),
+                    "BioPawnSaveObject" => Object::Pawn(next_element(&mut seq, &mut len, &self)?),
+                    "BioBaseSquadSaveObject" => Object::BaseSquad(next_element(&mut seq, &mut len, &self)?),
+                    "BioShopSaveObject" => Object::Shop(next_element(&mut seq, &mut len, &self)?),
+                    "BioInventorySaveObject" => Object::Inventory(next_element(&mut seq, &mut len, &self)?),
+                    "BioItemXModdableSaveObject" => Object::Item(next_element(&mut seq, &mut len, &self)?),
+                    "BioItemXModSaveObject" => Object::ItemMod(next_element(&mut seq, &mut len, &self)?),
+                    "BioArtPlaceableBehaviorSaveObject" => {
+                        Object::ArtPlaceableBehavior(next_element(&mut seq, &mut len, &self)?)
+                    }
+                    "BioArtPlaceableSaveObject" => Object::ArtPlaceable(next_element(&mut seq, &mut len, &self)?),
+                    "BioVehicleBehaviorSaveObject" => Object::VehicleBehavior(next_element(&mut seq, &mut len, &self)?),
+                    "BioVehicleSaveObject" => Object::Vehicle(next_element(&mut seq, &mut len, &self)?),
+                    "BioWorldInfoSaveObject" => Object::World(next_element(&mut seq, &mut len, &self)?),
+                    _ => return Err(de::Error::unknown_variant(&class_name, CLASS_NAMES)),
                 };
 
                 Ok(BaseObject {
@@ -84,7 +184,40 @@ impl<'de> Deserialize<'de> for BaseObject {
     }
 }
 
-#[derive(Serialize)]
+// Reads an object of unknown class as raw bytes, given the size of its class
+struct UnknownObject(usize);
+
+impl<'de> de::DeserializeSeed<'de> for UnknownObject {
+    type Value = List<u8>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct UnknownObjectVisitor(usize);
+        impl<'de> de::Visitor<'de> for UnknownObjectVisitor {
+            type Value = List<u8>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an unknown object of {} bytes", self.0)
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::SeqAccess<'de>,
+            {
+                let mut raw = Vec::with_capacity(self.0);
+                while let Some(byte) = seq.next_element()? {
+                    raw.push(byte);
+                }
+                Ok(List(raw))
+            }
+        }
+        deserializer.deserialize_tuple(self.0, UnknownObjectVisitor(self.0))
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 pub enum Object {
     PawnBehavior(Box<PawnBehavior>),
     Pawn(Box<Pawn>),
@@ -98,6 +231,7 @@ pub enum Object {
     VehicleBehavior(Box<VehicleBehavior>),
     Vehicle(Box<Vehicle>),
     World(Box<World>),
+    Unknown(List<u8>),
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -6,7 +6,7 @@ use self::{legacy::*, player::*, squad::*};
 
 use std::{fmt, io::Read};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use flate2::{
     read::{ZlibDecoder, ZlibEncoder},
     Compression,
@@ -19,6 +19,7 @@ use serde::{
 
 use crate::{
     save_data::{
+        next_element,
         shared::{
             plot::{Codex, Journal, PlotTable},
             Rotator, SaveTimeStamp, Vector3D,
@@ -44,11 +45,156 @@ pub struct Me1LeSaveGame {
     _uncompressed_size: u32,
 }
 
+impl Me1LeSaveGame {
+    // The uncompressed save data, to compare saves regardless of the compression
+    pub fn decompress(input: &[u8]) -> Result<Vec<u8>> {
+        let (_, headers, mut offset) = read_chunk_headers(input)?;
+
+        let mut uncompressed = Vec::new();
+        for chunk in &headers[1..] {
+            let compressed_size = chunk.compressed_size as usize;
+            let compressed = input
+                .get(offset..offset + compressed_size)
+                .context("Unexpected end of file")?;
+            ZlibDecoder::new(compressed).read_to_end(&mut uncompressed)?;
+            offset += compressed_size;
+        }
+        Ok(uncompressed)
+    }
+
+    // Inconsistencies between the chunk headers and the chunks, read from the file alone: the
+    // deserialization stops at the first chunk that does not match its header, this reports all of them
+    pub fn chunk_issues(input: &[u8]) -> Result<Vec<String>> {
+        let (block_size, mut chunks, mut offset) = read_chunk_headers(input)?;
+        let full_header = chunks.remove(0);
+        let mut issues = Vec::new();
+
+        let compressed_size: u32 = chunks.iter().map(|chunk| chunk.compressed_size).sum();
+        if full_header.compressed_size != compressed_size {
+            issues.push(format!(
+                "total compressed size is {}, the chunks add up to {compressed_size}",
+                full_header.compressed_size
+            ));
+        }
+
+        let uncompressed_size: u32 = chunks.iter().map(|chunk| chunk.uncompressed_size).sum();
+        if full_header.uncompressed_size != uncompressed_size {
+            issues.push(format!(
+                "total uncompressed size is {}, the chunks add up to {uncompressed_size}",
+                full_header.uncompressed_size
+            ));
+        }
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            if chunk.uncompressed_size > block_size {
+                issues.push(format!(
+                    "chunk #{i} is {} bytes, larger than the block size ({block_size})",
+                    chunk.uncompressed_size
+                ));
+            }
+
+            let compressed_size = chunk.compressed_size as usize;
+            let compressed = match input.get(offset..offset + compressed_size) {
+                Some(compressed) => compressed,
+                None => {
+                    issues.push(format!("chunk #{i} runs past the end of the file"));
+                    return Ok(issues);
+                }
+            };
+            offset += compressed_size;
+
+            let mut uncompressed = Vec::new();
+            match ZlibDecoder::new(compressed).read_to_end(&mut uncompressed) {
+                Ok(size) if size != chunk.uncompressed_size as usize => issues.push(format!(
+                    "chunk #{i} decompresses to {size} bytes, its header says {}",
+                    chunk.uncompressed_size
+                )),
+                Ok(_) => (),
+                Err(err) => issues.push(format!("chunk #{i} does not decompress: {err}")),
+            }
+        }
+
+        // Checksum, compression flag and uncompressed size
+        match input.len() - offset {
+            12 => {
+                let trailing_size = read_u32(input, offset + 8)?;
+                if trailing_size != full_header.uncompressed_size {
+                    issues.push(format!(
+                        "trailing uncompressed size is {trailing_size}, the header says {}",
+                        full_header.uncompressed_size
+                    ));
+                }
+            }
+            trailing => issues.push(format!("{trailing} bytes after the chunks, expected 12")),
+        }
+        Ok(issues)
+    }
+}
+
+fn read_u32(input: &[u8], offset: usize) -> Result<u32> {
+    let bytes = input.get(offset..offset + 4).context("Unexpected end of file")?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+// Block size, full header then chunk headers, and the offset of the first chunk
+fn read_chunk_headers(input: &[u8]) -> Result<(u32, Vec<ChunkHeader>, usize)> {
+    // Magic number, block size
+    let block_size = read_u32(input, 4)?;
+    let mut offset = 8;
+
+    let mut headers = Vec::new();
+    loop {
+        let header = ChunkHeader {
+            compressed_size: read_u32(input, offset)?,
+            uncompressed_size: read_u32(input, offset + 4)?,
+        };
+        offset += 8;
+
+        let is_last = !headers.is_empty() && header.uncompressed_size < block_size;
+        headers.push(header);
+        if is_last {
+            break;
+        }
+    }
+    Ok((block_size, headers, offset))
+}
+
 impl<'de> Deserialize<'de> for Me1LeSaveGame {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            // Uncompressed form, chunk headers are rebuilt on serialization
+            #[derive(Deserialize)]
+            #[serde(rename = "Me1LeSaveGame")]
+            struct HumanReadable {
+                magic_number: Me1LeMagicNumber,
+                block_size: u32,
+                save_data: Me1LeSaveData,
+                checksum: u32,
+                compression_flag: u32,
+            }
+
+            let HumanReadable {
+                magic_number,
+                block_size,
+                save_data,
+                checksum,
+                compression_flag,
+            } = Deserialize::deserialize(deserializer)?;
+
+            return Ok(Me1LeSaveGame {
+                magic_number,
+                block_size,
+                _headers: Vec::new().into(),
+                save_data,
+                checksum,
+                compression_flag,
+                _uncompressed_size: 0,
+            });
+        }
+
         struct Me1LeSaveGameVisitor;
         impl<'de> de::Visitor<'de> for Me1LeSaveGameVisitor {
             type Value = Me1LeSaveGame;
@@ -61,23 +207,24 @@ impl<'de> Deserialize<'de> for Me1LeSaveGame {
             where
                 A: de::SeqAccess<'de>,
             {
-                let magic_number = seq.next_element()?.unwrap();
-                let block_size = seq.next_element()?.unwrap();
+                let mut len = 0;
+                let magic_number = next_element(&mut seq, &mut len, &self)?;
+                let block_size = next_element(&mut seq, &mut len, &self)?;
 
                 // Headers
                 let mut headers = Vec::new();
                 {
                     let full_header = ChunkHeader {
-                        compressed_size: seq.next_element()?.unwrap(),
-                        uncompressed_size: seq.next_element()?.unwrap(),
+                        compressed_size: next_element(&mut seq, &mut len, &self)?,
+                        uncompressed_size: next_element(&mut seq, &mut len, &self)?,
                     };
                     headers.push(full_header);
 
                     let mut finished = false;
                     while !finished {
                         let header = ChunkHeader {
-                            compressed_size: seq.next_element()?.unwrap(),
-                            uncompressed_size: seq.next_element()?.unwrap(),
+                            compressed_size: next_element(&mut seq, &mut len, &self)?,
+                            uncompressed_size: next_element(&mut seq, &mut len, &self)?,
                         };
                         if header.uncompressed_size < block_size {
                             finished = true;
@@ -93,19 +240,30 @@ impl<'de> Deserialize<'de> for Me1LeSaveGame {
                     for header in &headers[1..] {
                         let mut compressed = Vec::new();
                         for _ in 0..header.compressed_size {
-                            compressed.push(seq.next_element()?.unwrap());
+                            compressed.push(next_element(&mut seq, &mut len, &self)?);
                         }
 
                         let mut z = ZlibDecoder::new(&compressed[..]);
-                        z.read_to_end(&mut uncompressed).map_err(de::Error::custom)?;
+                        let chunk_size = z.read_to_end(&mut uncompressed).map_err(de::Error::custom)?;
+                        if chunk_size != header.uncompressed_size as usize {
+                            return Err(de::Error::invalid_length(
+                                chunk_size,
+                                &format!("a chunk of {} bytes", header.uncompressed_size).as_str(),
+                            ));
+                        }
                     }
 
-                    unreal::Deserializer::from_bytes(&uncompressed).map_err(de::Error::custom)?
+                    unreal::Deserializer::from_bytes(&uncompressed).map_err(|err: unreal::Error| {
+                        err.nested(unreal::Nested::Extracted {
+                            field: "save_data",
+                            within: "the decompressed save data",
+                        })
+                    })?
                 };
 
-                let checksum = seq.next_element()?.unwrap();
-                let compression_flag = seq.next_element()?.unwrap();
-                let uncompressed_size = seq.next_element()?.unwrap();
+                let checksum = next_element(&mut seq, &mut len, &self)?;
+                let compression_flag = next_element(&mut seq, &mut len, &self)?;
+                let uncompressed_size = next_element(&mut seq, &mut len, &self)?;
 
                 Ok(Me1LeSaveGame {
                     magic_number,
@@ -137,6 +295,16 @@ impl serde::Serialize for Me1LeSaveGame {
             _uncompressed_size,
         } = self;
 
+        if serializer.is_human_readable() {
+            let mut s = serializer.serialize_struct("Me1LeSaveGame", 5)?;
+            s.serialize_field("magic_number", magic_number)?;
+            s.serialize_field("block_size", block_size)?;
+            s.serialize_field("save_data", save_data)?;
+            s.serialize_field("checksum", checksum)?;
+            s.serialize_field("compression_flag", compression_flag)?;
+            return s.end();
+        }
+
         let mut headers = Vec::new();
 
         let uncompressed = unreal::Serializer::to_vec(save_data).map_err(ser::Error::custom)?;
@@ -183,6 +351,7 @@ impl serde::Serialize for Me1LeSaveGame {
 }
 
 #[derive(Serialize)]
+#[serde(transparent)]
 pub struct Me1LeMagicNumber(u32);
 
 impl<'de> Deserialize<'de> for Me1LeMagicNumber {
@@ -190,9 +359,19 @@ impl<'de> Deserialize<'de> for Me1LeMagicNumber {
     where
         D: Deserializer<'de>,
     {
+        const MAGIC_NUMBER: [u8; 4] = [0xC1, 0x83, 0x2A, 0x9E];
+
+        if deserializer.is_human_readable() {
+            let magic_number: u32 = Deserialize::deserialize(deserializer)?;
+            if magic_number != u32::from_le_bytes(MAGIC_NUMBER) {
+                return Err(de::Error::custom("Wrong magic number"));
+            }
+            return Ok(Self(magic_number));
+        }
+
         let version: [u8; 4] = Deserialize::deserialize(deserializer)?;
 
-        if version != [0xC1, 0x83, 0x2A, 0x9E] {
+        if version != MAGIC_NUMBER {
             return Err(de::Error::custom("Wrong magic number"));
         }
 
@@ -208,10 +387,10 @@ pub struct Me1LeSaveData {
     pub plot: PlotTable,
     journal: Journal,
     codex: Codex,
-    timestamp: SaveTimeStamp,
-    seconds_played: i32,
+    pub timestamp: SaveTimeStamp,
+    pub seconds_played: i32,
     pub player: Player,
-    base_level_name: String,
+    pub base_level_name: String,
     map_name: String,
     parent_map_name: String,
     location: Vector3D,
@@ -223,6 +402,7 @@ pub struct Me1LeSaveData {
 }
 
 #[derive(Serialize)]
+#[serde(transparent)]
 pub struct Me1LeVersion(i32);
 
 impl<'de> Deserialize<'de> for Me1LeVersion {
@@ -255,6 +435,10 @@ impl<'de> Deserialize<'de> for NoExport {
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            return Deserialize::deserialize(deserializer).map(NoExport);
+        }
+
         struct NoExportVisitor;
         impl<'de> de::Visitor<'de> for NoExportVisitor {
             type Value = NoExport;
@@ -267,7 +451,16 @@ impl<'de> Deserialize<'de> for NoExport {
             where
                 D: Deserializer<'de>,
             {
-                let no_export_data: NoExportData = Deserialize::deserialize(deserializer)?;
+                // The section runs to the end of the save data
+                let raw: List<u8> = Deserialize::deserialize(deserializer)?;
+                let no_export_data = match unreal::Deserializer::from_bytes(&raw) {
+                    Ok(legacy_data) => NoExportData::Known(Box::new(legacy_data)),
+                    Err(unreal::Error {
+                        kind: unreal::ErrorKind::UnknownClass(_),
+                        ..
+                    }) => NoExportData::Unknown(raw),
+                    Err(err) => return Err(err.nested(unreal::Nested::Read { len: raw.len() })),
+                };
                 Ok(NoExport(Some(no_export_data)))
             }
 
@@ -287,15 +480,29 @@ impl serde::Serialize for NoExport {
     where
         S: Serializer,
     {
+        if serializer.is_human_readable() {
+            return self.0.serialize(serializer);
+        }
+
         match self.0 {
-            Some(ref no_export_data) => no_export_data.serialize(serializer),
+            Some(NoExportData::Known(ref legacy_data)) => legacy_data.serialize(serializer),
+            Some(NoExportData::Unknown(ref raw)) => raw.serialize(serializer),
             None => serializer.serialize_unit(),
         }
     }
 }
 
+// Legacy objects have no size, if one of them has an unknown class whose size is not given
+// (see `legacy::ClassSizes`) the whole section is kept as raw bytes so the save can still be
+// edited and saved
+#[derive(Deserialize, Serialize)]
+pub enum NoExportData {
+    Known(Box<LegacyData>),
+    Unknown(List<u8>),
+}
+
 #[derive(Deserialize, Serialize)]
-pub struct NoExportData {
+pub struct LegacyData {
     legacy_maps: IndexMap<String, Map>,
     mako: Vehicle,
 }
@@ -317,7 +524,7 @@ mod test {
     use crc::{Crc, CRC_32_BZIP2};
 
     use super::*;
-    use crate::unreal;
+    use crate::{save_game::SaveKind, unreal};
 
     #[test]
     fn deserialize_serialize() -> Result<()> {
@@ -390,6 +597,79 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn chunk_issues() -> Result<()> {
+        let mut input = fs::read("test/ME1LeSave.pcsav")?;
+        assert!(Me1LeSaveGame::chunk_issues(&input)?.is_empty());
+
+        // Uncompressed size of both chunks, after the magic number, the block size and the full header
+        for offset in [20, 28] {
+            let size = read_u32(&input, offset)? + 1;
+            input[offset..offset + 4].copy_from_slice(&size.to_le_bytes());
+        }
+        assert!(unreal::Deserializer::from_bytes::<Me1LeSaveGame>(&input).is_err());
+
+        let issues = Me1LeSaveGame::chunk_issues(&input)?;
+        assert_eq!(issues.len(), 4, "{issues:?}");
+        assert!(issues[0].starts_with("total uncompressed size"));
+        assert!(issues[1].starts_with("chunk #0 is 262145 bytes, larger than the block size"));
+        assert!(issues[2].starts_with("chunk #0 decompresses to 262144 bytes"));
+        assert!(issues[3].starts_with("chunk #1 decompresses to 247017 bytes"));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_legacy_class() -> Result<()> {
+        let mut input = fs::read("test/ME1LeSave.uncompressed")?;
+
+        // Same length class names, unknown to the editor, one in the middle of a level and the world at the end
+        // Only the size of the first one is given
+        let patch = |input: &mut Vec<u8>, class_name: &[u8], nth: usize, unknown: &[u8]| {
+            let idx = input
+                .windows(class_name.len())
+                .enumerate()
+                .filter(|(_, window)| *window == class_name)
+                .nth(nth)
+                .unwrap()
+                .0;
+            input[idx..idx + class_name.len()].copy_from_slice(unknown);
+        };
+        patch(&mut input, b"BioItemXModSaveObject", 47, b"BioModdedThingSaveObj");
+
+        let mut class_sizes = ClassSizes::default();
+        class_sizes.insert("BioModdedThingSaveObj", 17);
+        let deserialize = |input: &[u8], class_sizes: &ClassSizes| -> Result<Box<Me1LeSaveData>> {
+            match SaveKind::deserialize_with(input.to_vec(), class_sizes)? {
+                SaveKind::MassEffect1LePs4(save_data) => Ok(save_data),
+                _ => unreachable!(),
+            }
+        };
+
+        // Without the size the section is kept as raw bytes
+        let save_data = deserialize(&input, &ClassSizes::default())?;
+        assert!(matches!(save_data.no_export.0, Some(NoExportData::Unknown(_))));
+
+        // With it the object is kept as raw bytes, the others around it stay typed
+        let save_data = deserialize(&input, &class_sizes)?;
+        let ron = ron::to_string(&save_data.no_export.0)?;
+        assert!(ron.starts_with("Some(Known("));
+        assert_eq!(ron.matches("Unknown(").count(), 1);
+        assert_eq!(ron.matches("BioModdedThingSaveObj").count(), 1);
+        assert_eq!(ron.matches("ItemMod(").count(), 93);
+
+        let output = unreal::Serializer::to_vec(&save_data)?;
+        assert!(input == output);
+
+        // A single unknown size is enough for the whole section to be kept as raw bytes
+        patch(&mut input, b"BioWorldInfoSaveObject", 0, b"BioModdedStuffSaveObje");
+        let save_data = deserialize(&input, &class_sizes)?;
+        assert!(matches!(save_data.no_export.0, Some(NoExportData::Unknown(_))));
+
+        let output = unreal::Serializer::to_vec(&save_data)?;
+        assert!(input == output);
+        Ok(())
+    }
+
     // #[test]
     // fn uncompress() -> Result<()> {
     //     let input = fs::read("test/ME1Le_Export.pcsav")?;
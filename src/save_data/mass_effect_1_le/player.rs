@@ -62,7 +62,7 @@ pub struct Player {
     secondary_weapon: String,
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
 pub enum Me1LeClass {
     Soldier,
     Engineer,
@@ -99,8 +99,9 @@ pub struct Inventory {
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Deserialize, Serialize, Copy, Clone)]
+#[derive(Deserialize, Serialize, Copy, Clone, Default)]
 pub enum ItemLevel {
+    #[default]
     None,
     I,
     II,
@@ -114,12 +115,6 @@ pub enum ItemLevel {
     X,
 }
 
-impl Default for ItemLevel {
-    fn default() -> Self {
-        ItemLevel::None
-    }
-}
-
 #[derive(Deserialize, Serialize, Default)]
 pub struct Item {
     pub item_id: i32,
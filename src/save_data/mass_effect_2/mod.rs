@@ -1,6 +1,6 @@
 mod galaxy_map;
 pub mod player;
-mod squad;
+pub mod squad;
 
 use self::{galaxy_map::*, player::*, squad::*};
 
@@ -17,12 +17,12 @@ use super::Guid;
 pub struct Me2SaveGame {
     _version: Me2Version,
     debug_name: String,
-    seconds_played: f32,
+    pub seconds_played: f32,
     disc: i32,
-    base_level_name: String,
+    pub base_level_name: String,
     pub difficulty: Difficulty,
     pub end_game_state: EndGameState,
-    timestamp: SaveTimeStamp,
+    pub timestamp: SaveTimeStamp,
     location: Vector3D,
     rotation: Rotator,
     current_loading_tip: i32,
@@ -32,7 +32,7 @@ pub struct Me2SaveGame {
     doors: Vec<Door>,
     pawns: Vec<Guid>,
     pub player: Player,
-    squad: Vec<Henchman>,
+    pub squad: Vec<Henchman>,
     pub plot: PlotTable,
     journal: Journal,
     codex: Codex,
@@ -41,7 +41,19 @@ pub struct Me2SaveGame {
     dependant_dlcs: Vec<DependentDlc>,
 }
 
+impl Me2SaveGame {
+    pub fn is_xbox360(&self) -> bool {
+        self._version.is_xbox360
+    }
+
+    // The platform is not part of the human readable format
+    pub fn set_xbox360(&mut self, is_xbox360: bool) {
+        self._version.is_xbox360 = is_xbox360;
+    }
+}
+
 #[derive(Serialize)]
+#[serde(transparent)]
 pub struct Me2Version {
     version: i32,
     #[serde(skip)]
@@ -55,6 +67,19 @@ impl<'de> Deserialize<'de> for Me2Version {
     {
         const GAME_VERSION: i32 = 29;
 
+        if deserializer.is_human_readable() {
+            let version: i32 = Deserialize::deserialize(deserializer)?;
+            if version != GAME_VERSION {
+                return Err(de::Error::custom(
+                    "Wrong save version, please use a save from the latest version of the game",
+                ));
+            }
+            return Ok(Self {
+                version,
+                is_xbox360: false,
+            });
+        }
+
         let bytes: [u8; 4] = Deserialize::deserialize(deserializer)?;
         let version_le = i32::from_le_bytes(bytes);
         let version_be = i32::from_be_bytes(bytes);
@@ -81,12 +106,12 @@ impl<'de> Deserialize<'de> for Me2Version {
 pub struct Me2LeSaveGame {
     _version: Me2LeVersion,
     debug_name: String,
-    seconds_played: f32,
+    pub seconds_played: f32,
     disc: i32,
-    base_level_name: String,
+    pub base_level_name: String,
     pub difficulty: Difficulty,
     pub end_game_state: EndGameState,
-    timestamp: SaveTimeStamp,
+    pub timestamp: SaveTimeStamp,
     location: Vector3D,
     rotation: Rotator,
     current_loading_tip: i32,
@@ -97,7 +122,7 @@ pub struct Me2LeSaveGame {
     pawns: Vec<Guid>,
     pub player: Player,
     me1_import_bonus: Me1ImportBonus,
-    squad: Vec<Henchman>,
+    pub squad: Vec<Henchman>,
     pub plot: PlotTable,
     journal: Journal,
     codex: Codex,
@@ -118,6 +143,7 @@ struct Me1ImportBonus {
 }
 
 #[derive(Serialize)]
+#[serde(transparent)]
 pub struct Me2LeVersion(i32);
 
 impl<'de> Deserialize<'de> for Me2LeVersion {
@@ -137,7 +163,7 @@ impl<'de> Deserialize<'de> for Me2LeVersion {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
 pub enum Difficulty {
     Casual,
     Normal,
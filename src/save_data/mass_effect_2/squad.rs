@@ -5,7 +5,7 @@ use crate::save_data::shared::player::WeaponLoadout;
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct Henchman {
-    tag: String,
+    pub tag: String,
     powers: Vec<Power>,
     character_level: i32,
     talent_points: i32,
@@ -1,7 +1,7 @@
 mod galaxy_map;
 pub mod player;
 pub mod plot;
-mod squad;
+pub mod squad;
 
 use crate::IndexMap;
 
@@ -20,13 +20,13 @@ use super::Guid;
 pub struct Me3SaveGame {
     _version: Me3Version,
     debug_name: String,
-    seconds_played: f32,
+    pub seconds_played: f32,
     disc: i32,
-    base_level_name: String,
+    pub base_level_name: String,
     base_level_name_display_override: String,
     pub difficulty: Difficulty,
     pub end_game_state: EndGameState,
-    timestamp: SaveTimeStamp,
+    pub timestamp: SaveTimeStamp,
     location: Vector3D,
     rotation: Rotator,
     current_loading_tip: i32,
@@ -37,11 +37,12 @@ pub struct Me3SaveGame {
     placeables: Vec<Placeable>,
     pawns: Vec<Guid>,
     pub player: Player,
-    squad: Vec<Henchman>,
+    pub squad: Vec<Henchman>,
     pub plot: PlotTable,
     journal: Journal,
     codex: Codex,
-    _me1_plot: Me1PlotTable,
+    #[serde(rename = "_me1_plot")]
+    pub me1_plot: Me1PlotTable,
     pub player_variables: IndexMap<String, i32>,
     galaxy_map: GalaxyMap,
     dependant_dlcs: Vec<DependentDlc>,
@@ -52,7 +53,21 @@ pub struct Me3SaveGame {
     saved_objective_text: i32,
 }
 
+impl Me3SaveGame {
+    pub fn is_xbox360(&self) -> bool {
+        self._version.is_xbox360
+    }
+
+    // The platform is not part of the human readable format
+    pub fn set_xbox360(&mut self, is_xbox360: bool) {
+        self._version.is_xbox360 = is_xbox360;
+    }
+}
+
+const GAME_VERSION: i32 = 59;
+
 #[derive(Serialize)]
+#[serde(transparent)]
 pub struct Me3Version {
     version: i32,
     #[serde(skip)]
@@ -64,7 +79,19 @@ impl<'de> Deserialize<'de> for Me3Version {
     where
         D: Deserializer<'de>,
     {
-        const GAME_VERSION: i32 = 59;
+        let wrong_version =
+            || de::Error::custom("Wrong save version, please use a save from the latest version of the game");
+
+        if deserializer.is_human_readable() {
+            let version: i32 = Deserialize::deserialize(deserializer)?;
+            if version != GAME_VERSION {
+                return Err(wrong_version());
+            }
+            return Ok(Self {
+                version,
+                is_xbox360: false,
+            });
+        }
 
         let bytes: [u8; 4] = Deserialize::deserialize(deserializer)?;
         let version_le = i32::from_le_bytes(bytes);
@@ -81,14 +108,12 @@ impl<'de> Deserialize<'de> for Me3Version {
                 is_xbox360: true,
             })
         } else {
-            Err(de::Error::custom(
-                "Wrong save version, please use a save from the latest version of the game",
-            ))
+            Err(wrong_version())
         }
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
 pub enum Difficulty {
     Narrative,
     Casual,
@@ -104,18 +129,13 @@ pub struct Placeable {
     is_deactivated: PlaceableState,
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
 pub enum PlaceableState {
+    #[default]
     No,
     Yes,
 }
 
-impl Default for PlaceableState {
-    fn default() -> Self {
-        PlaceableState::No
-    }
-}
-
 #[derive(Deserialize, Serialize, Default)]
 struct DependentDlc {
     id: i32,
@@ -149,20 +169,15 @@ struct ObjectiveMarker {
     marker_icon_type: ObjectiveMarkerIconType,
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
 enum ObjectiveMarkerIconType {
+    #[default]
     None,
     Attack,
     Supply,
     Alert,
 }
 
-impl Default for ObjectiveMarkerIconType {
-    fn default() -> Self {
-        ObjectiveMarkerIconType::None
-    }
-}
-
 #[cfg(test)]
 mod test {
     use std::fs;
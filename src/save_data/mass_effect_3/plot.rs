@@ -1,7 +1,9 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    save_data::shared::plot::{BitVec, PlotCodex},
+    plot_db::PlotKind,
+    save_data::shared::plot::{check_plot_id, BitVec, PlotAccess, PlotCodex},
     IndexMap,
 };
 
@@ -12,6 +14,59 @@ pub struct PlotTable {
     pub floats: IndexMap<i32, f32>,
 }
 
+impl PlotAccess for PlotTable {
+    fn boolean(&self, id: usize) -> bool {
+        self.booleans.get(id).map(|b| *b).unwrap_or_default()
+    }
+
+    // The ids are stored as i32, an id out of their range is not in the table
+    fn integer(&self, id: usize) -> i32 {
+        (i32::try_from(id).ok())
+            .and_then(|id| self.integers.get(&id).copied())
+            .unwrap_or_default()
+    }
+
+    fn float(&self, id: usize) -> f32 {
+        (i32::try_from(id).ok())
+            .and_then(|id| self.floats.get(&id).copied())
+            .unwrap_or_default()
+    }
+
+    fn set_boolean(&mut self, id: usize, value: bool) -> Result<()> {
+        check_plot_id(id)?;
+        if id >= self.booleans.len() {
+            self.booleans.resize(id + 1, false);
+        }
+        self.booleans.set(id, value);
+        Ok(())
+    }
+
+    fn set_integer(&mut self, id: usize, value: i32) -> Result<()> {
+        check_plot_id(id)?;
+        self.integers.insert(i32::try_from(id)?, value);
+        Ok(())
+    }
+
+    fn set_float(&mut self, id: usize, value: f32) -> Result<()> {
+        check_plot_id(id)?;
+        self.floats.insert(i32::try_from(id)?, value);
+        Ok(())
+    }
+
+    fn ids(&self, kind: PlotKind) -> Vec<usize> {
+        // Negative ids can't be addressed
+        fn ids<T>(variables: &IndexMap<i32, T>) -> Vec<usize> {
+            variables.keys().filter_map(|&id| usize::try_from(id).ok()).collect()
+        }
+
+        match kind {
+            PlotKind::Boolean => (0..self.booleans.len()).collect(),
+            PlotKind::Integer => ids(&self.integers),
+            PlotKind::Float => ids(&self.floats),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Journal {
     quest_progress_counter: i32,
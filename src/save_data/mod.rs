@@ -1,3 +1,4 @@
+pub mod mass_effect_1;
 pub mod mass_effect_1_le;
 pub mod mass_effect_2;
 pub mod mass_effect_3;
@@ -6,9 +7,26 @@ pub mod shared;
 use std::fmt;
 
 use anyhow::Result;
-use serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de,
+    ser::{SerializeSeq, SerializeTuple},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use uuid::Uuid;
 
+// `SeqAccess::next_element` for required elements, `len` counts the elements already read
+fn next_element<'de, A, T>(seq: &mut A, len: &mut usize, expected: &dyn de::Expected) -> Result<T, A::Error>
+where
+    A: de::SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    let element = seq
+        .next_element()?
+        .ok_or_else(|| de::Error::invalid_length(*len, expected))?;
+    *len += 1;
+    Ok(element)
+}
+
 // Implémentation des dummy
 #[derive(Clone)]
 pub struct Dummy<const BYTE_LEN: usize>([u8; BYTE_LEN]);
@@ -37,10 +55,8 @@ impl<'de, const BYTE_LEN: usize> Deserialize<'de> for Dummy<BYTE_LEN> {
                 A: de::SeqAccess<'de>,
             {
                 let mut result = [0_u8; BYTE_LEN];
-                let mut i = 0;
-                while let Some(element) = seq.next_element()? {
-                    result[i] = element;
-                    i += 1;
+                for (i, byte) in result.iter_mut().enumerate() {
+                    *byte = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
                 }
                 Ok(Dummy(result))
             }
@@ -54,7 +70,15 @@ impl<const BYTE_LEN: usize> serde::Serialize for Dummy<BYTE_LEN> {
     where
         S: Serializer,
     {
-        serializer.serialize_bytes(&self.0)
+        if serializer.is_human_readable() {
+            let mut s = serializer.serialize_tuple(BYTE_LEN)?;
+            for byte in &self.0 {
+                s.serialize_element(byte)?;
+            }
+            s.end()
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
     }
 }
 
@@ -78,6 +102,10 @@ impl<'de> Deserialize<'de> for List<u8> {
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            return Vec::deserialize(deserializer).map(List);
+        }
+
         struct ByteListVisitor;
         impl<'de> de::Visitor<'de> for ByteListVisitor {
             type Value = List<u8>;
@@ -127,6 +155,12 @@ impl<'de> Deserialize<'de> for Guid {
     where
         D: Deserializer<'de>,
     {
+        if deserializer.is_human_readable() {
+            let hyphenated: String = Deserialize::deserialize(deserializer)?;
+            let uuid = Uuid::parse_str(&hyphenated).map_err(de::Error::custom)?;
+            return Ok(Guid(uuid));
+        }
+
         let (d1, d2, d3, d4): (u32, u16, u16, [u8; 8]) = Deserialize::deserialize(deserializer)?;
         Ok(Guid(Uuid::from_fields(d1, d2, d3, &d4)))
     }
@@ -137,6 +171,10 @@ impl serde::Serialize for Guid {
     where
         S: Serializer,
     {
+        if serializer.is_human_readable() {
+            return serializer.serialize_str(&self.0.as_hyphenated().to_string());
+        }
+
         serde::Serialize::serialize(&self.0.as_fields(), serializer)
     }
 }
@@ -30,7 +30,7 @@ enum PlayerAppearanceType {
     Full,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub struct HeadMorph {
     pub hair_mesh: String,
     pub accessory_mesh: Vec<String>,
@@ -45,7 +45,7 @@ pub struct HeadMorph {
     pub texture_parameters: IndexMap<String, String>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy, PartialEq)]
 pub struct LinearColor {
     pub r: f32,
     pub g: f32,
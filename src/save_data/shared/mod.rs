@@ -2,11 +2,13 @@ pub mod appearance;
 pub mod player;
 pub mod plot;
 
+use std::fmt;
+
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::Guid;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 #[repr(u32)]
 pub enum EndGameState {
     NotFinished,
@@ -48,11 +50,27 @@ pub struct SaveTimeStamp {
     year: i32,
 }
 
-#[derive(Deserialize, Serialize, Default)]
+impl fmt::Display for SaveTimeStamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let SaveTimeStamp {
+            seconds_since_midnight,
+            day,
+            month,
+            year,
+        } = *self;
+
+        let hours = seconds_since_midnight / 3600;
+        let minutes = seconds_since_midnight % 3600 / 60;
+        let seconds = seconds_since_midnight % 60;
+        write!(f, "{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02}")
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, PartialEq)]
 pub struct Vector3D {
-    x: f32,
-    y: f32,
-    z: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -61,11 +79,11 @@ pub struct Vector2D {
     y: f32,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Rotator {
-    pitch: i32,
-    yaw: i32,
-    roll: i32,
+    pub pitch: i32,
+    pub yaw: i32,
+    pub roll: i32,
 }
 
 #[derive(Deserialize, Serialize, Default)]
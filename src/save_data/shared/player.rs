@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Copy, Clone)]
+#[derive(Deserialize, Serialize, Copy, Clone, Debug)]
 pub enum Origin {
     None,
     Spacer,
@@ -8,7 +8,7 @@ pub enum Origin {
     Earthborn,
 }
 
-#[derive(Deserialize, Serialize, Copy, Clone)]
+#[derive(Deserialize, Serialize, Copy, Clone, Debug)]
 pub enum Notoriety {
     None,
     Survivor,
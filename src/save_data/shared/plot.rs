@@ -1,7 +1,9 @@
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use bitvec::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+use crate::plot_db::PlotKind;
+
 #[derive(Deref, DerefMut)]
 pub struct BitVec(bitvec::vec::BitVec<u32, Lsb0>);
 
@@ -26,6 +28,28 @@ impl serde::Serialize for BitVec {
     }
 }
 
+// The games have a few tens of thousands of plot variables, a much higher id is most likely a
+// typo and would grow the tables to gigabytes
+pub const MAX_PLOT_ID: usize = 0xF_FFFF;
+
+pub fn check_plot_id(id: usize) -> Result<()> {
+    ensure!(id <= MAX_PLOT_ID, "Plot id {id} is out of range, the highest is {MAX_PLOT_ID}");
+    Ok(())
+}
+
+// Uniform access to the plot variables whatever the storage of the table
+pub trait PlotAccess {
+    fn boolean(&self, id: usize) -> bool;
+    fn integer(&self, id: usize) -> i32;
+    fn float(&self, id: usize) -> f32;
+    // Fail if the id is out of range
+    fn set_boolean(&mut self, id: usize, value: bool) -> Result<()>;
+    fn set_integer(&mut self, id: usize, value: i32) -> Result<()>;
+    fn set_float(&mut self, id: usize, value: f32) -> Result<()>;
+    // Ids of the variables stored in the table, the others are false or 0
+    fn ids(&self, kind: PlotKind) -> Vec<usize>;
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct PlotTable {
     pub booleans: BitVec,
@@ -33,6 +57,55 @@ pub struct PlotTable {
     pub floats: Vec<f32>,
 }
 
+impl PlotAccess for PlotTable {
+    fn boolean(&self, id: usize) -> bool {
+        self.booleans.get(id).map(|b| *b).unwrap_or_default()
+    }
+
+    fn integer(&self, id: usize) -> i32 {
+        self.integers.get(id).copied().unwrap_or_default()
+    }
+
+    fn float(&self, id: usize) -> f32 {
+        self.floats.get(id).copied().unwrap_or_default()
+    }
+
+    fn set_boolean(&mut self, id: usize, value: bool) -> Result<()> {
+        check_plot_id(id)?;
+        if id >= self.booleans.len() {
+            self.booleans.resize(id + 1, false);
+        }
+        self.booleans.set(id, value);
+        Ok(())
+    }
+
+    fn set_integer(&mut self, id: usize, value: i32) -> Result<()> {
+        check_plot_id(id)?;
+        if id >= self.integers.len() {
+            self.integers.resize(id + 1, 0);
+        }
+        self.integers[id] = value;
+        Ok(())
+    }
+
+    fn set_float(&mut self, id: usize, value: f32) -> Result<()> {
+        check_plot_id(id)?;
+        if id >= self.floats.len() {
+            self.floats.resize(id + 1, 0.0);
+        }
+        self.floats[id] = value;
+        Ok(())
+    }
+
+    fn ids(&self, kind: PlotKind) -> Vec<usize> {
+        match kind {
+            PlotKind::Boolean => (0..self.booleans.len()).collect(),
+            PlotKind::Integer => (0..self.integers.len()).collect(),
+            PlotKind::Float => (0..self.floats.len()).collect(),
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Journal {
     quest_progress_counter: i32,
@@ -0,0 +1,199 @@
+use std::{collections::BTreeSet, fmt, mem, path::Path};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use serde_yaml::Value;
+
+use crate::{
+    plot_db::{PlotDb, PlotGame, PlotKind},
+    plot_patch::PlotValue,
+    reflect::{self, Change, FieldPath, PathSegment},
+    save_game::{SaveGame, SaveKind},
+};
+
+// Every difference between two saves of the same game, plot variables are compared by id
+// and the other fields by path
+#[derive(Serialize)]
+pub struct SaveDiff {
+    pub plot: Vec<PlotDiff>,
+    pub fields: Vec<FieldDiff>,
+}
+
+#[derive(Serialize)]
+pub struct PlotDiff {
+    pub table: PlotGame,
+    pub kind: PlotKind,
+    pub id: usize,
+    // Resolved with the plot database
+    pub name: Option<String>,
+    pub old: PlotValue,
+    pub new: PlotValue,
+}
+
+impl fmt::Display for PlotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let PlotDiff {
+            table,
+            kind,
+            id,
+            ref name,
+            old,
+            new,
+        } = *self;
+
+        write!(f, "~ {table} {kind} {id}")?;
+        if let Some(name) = name {
+            write!(f, " ({name})")?;
+        }
+        write!(f, ": {old} -> {new}")
+    }
+}
+
+// `old` is `None` for an addition, `new` is `None` for a removal
+#[derive(Serialize)]
+pub struct FieldDiff {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl SaveDiff {
+    pub fn is_empty(&self) -> bool {
+        self.plot.is_empty() && self.fields.is_empty()
+    }
+}
+
+// Plot tables are the `{ booleans, integers, floats }` structs, wherever they are in the save
+fn plot_table_paths(value: &Value, path: &mut Vec<PathSegment>, paths: &mut Vec<FieldPath>) {
+    let children = match value {
+        Value::Tagged(tagged) => return plot_table_paths(&tagged.value, path, paths),
+        Value::Mapping(map) => map,
+        _ => return,
+    };
+
+    let mut keys: Vec<_> = children.keys().filter_map(Value::as_str).collect();
+    keys.sort_unstable();
+    if keys == ["booleans", "floats", "integers"] {
+        paths.push(FieldPath(path.clone()));
+        return;
+    }
+
+    // Plot tables are not in lists, lists are not walked
+    for (segment, child) in reflect::children(value) {
+        path.push(segment);
+        plot_table_paths(child, path, paths);
+        path.pop();
+    }
+}
+
+fn diff_plot(old: &SaveKind, new: &SaveKind, database_dir: Option<&Path>) -> Result<Vec<PlotDiff>> {
+    let mut diffs = Vec::new();
+
+    for ((table, old_plot), (_, new_plot)) in old.plot_tables().into_iter().zip(new.plot_tables()) {
+        let plot_db = PlotDb::load(table, database_dir)?;
+
+        for kind in [PlotKind::Boolean, PlotKind::Integer, PlotKind::Float] {
+            let ids: BTreeSet<_> = old_plot.ids(kind).into_iter().chain(new_plot.ids(kind)).collect();
+            for id in ids {
+                let old = PlotValue::get(old_plot, kind, id);
+                let new = PlotValue::get(new_plot, kind, id);
+                if old != new {
+                    diffs.push(PlotDiff {
+                        table,
+                        kind,
+                        id,
+                        name: plot_db.find(kind, id).map(|variable| variable.name.to_owned()),
+                        old,
+                        new,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(diffs)
+}
+
+pub fn diff(old: &SaveKind, new: &SaveKind, database_dir: Option<&Path>) -> Result<SaveDiff> {
+    if mem::discriminant(old) != mem::discriminant(new) {
+        bail!("The saves are not from the same game");
+    }
+
+    let plot = diff_plot(old, new, database_dir)?;
+
+    let old_tree = reflect::to_value(old)?;
+    let new_tree = reflect::to_value(new)?;
+
+    let mut plot_tables = Vec::new();
+    plot_table_paths(&old_tree, &mut Vec::new(), &mut plot_tables);
+
+    let fields = reflect::diff(&old_tree, &new_tree)
+        .into_iter()
+        .filter(|change| !plot_tables.iter().any(|table| change.path().0.starts_with(&table.0)))
+        .map(|change| match change {
+            Change::Added(path, new) => FieldDiff {
+                path: path.to_string(),
+                old: None,
+                new: Some(new),
+            },
+            Change::Removed(path, old) => FieldDiff {
+                path: path.to_string(),
+                old: Some(old),
+                new: None,
+            },
+            Change::Modified(path, old, new) => FieldDiff {
+                path: path.to_string(),
+                old: Some(old),
+                new: Some(new),
+            },
+        })
+        .collect();
+
+    Ok(SaveDiff { plot, fields })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn diff_saves() -> Result<()> {
+        let old = SaveKind::deserialize(fs::read("test/ME3Save.pcsav")?)?;
+        let mut new = SaveKind::deserialize(fs::read("test/ME3Save.pcsav")?)?;
+
+        assert!(diff(&old, &new, None)?.is_empty());
+
+        let (_, plot) = &mut new.plot_tables_mut()[0];
+        let was_set = plot.boolean(42);
+        plot.set_boolean(42, !was_set)?;
+        plot.set_integer(123456, 7)?;
+        reflect::edit(&mut new, |root| reflect::set(root, &"player.credits".parse()?, "42"))?;
+
+        let diff = diff(&old, &new, None)?;
+        assert_eq!(diff.plot.len(), 2);
+        assert!(matches!(
+            diff.plot[0],
+            PlotDiff { table: PlotGame::Me3, kind: PlotKind::Boolean, id: 42, new: PlotValue::Boolean(new), .. } if new != was_set
+        ));
+        assert!(matches!(
+            diff.plot[1],
+            PlotDiff {
+                id: 123456,
+                old: PlotValue::Integer(0),
+                new: PlotValue::Integer(7),
+                ..
+            }
+        ));
+
+        // Plot tables are not repeated as raw fields
+        let paths: Vec<_> = diff.fields.iter().map(|field| field.path.as_str()).collect();
+        assert_eq!(paths, ["player.credits"]);
+
+        let other_game = SaveKind::deserialize(fs::read("test/ME2Save.pcsav")?)?;
+        assert!(super::diff(&old, &other_game, None).is_err());
+
+        Ok(())
+    }
+}
@@ -1,21 +1,354 @@
-use std::path::Path;
+use std::{borrow::Cow, path::Path};
 
 use anyhow::{bail, Result};
 use crc::{Crc, CRC_32_BZIP2};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    plot_db::PlotGame,
     save_data::{
-        mass_effect_1_le::Me1LeMagicNumber,
+        mass_effect_1::{player::difficulty_name, Me1MagicNumber, Me1SaveGame},
+        mass_effect_1_le::{legacy::ClassSizes, Me1LeMagicNumber},
         mass_effect_1_le::{Me1LeSaveData, Me1LeSaveGame, Me1LeVersion},
         mass_effect_2::{Me2LeSaveGame, Me2LeVersion, Me2SaveGame, Me2Version},
         mass_effect_3::{Me3SaveGame, Me3Version},
+        shared::{appearance::HeadMorph, plot::PlotAccess, SaveTimeStamp},
     },
     unreal,
 };
 
-#[derive(From)]
+// Uniform access to the data every game has, `None` when a save does not have it
+pub trait SaveGame {
+    fn player_name(&self) -> Option<&str>;
+    fn level(&self) -> Option<i32>;
+    fn credits(&self) -> Option<i32>;
+    fn is_female(&self) -> Option<bool>;
+    fn difficulty(&self) -> Option<String>;
+    // Henchmen tags
+    fn squad(&self) -> Vec<&str>;
+    fn timestamp(&self) -> Option<&SaveTimeStamp>;
+    // `Some(None)` is the default Shepard
+    fn head_morph(&self) -> Option<Cow<'_, Option<HeadMorph>>>;
+    fn set_head_morph(&mut self, head_morph: Option<HeadMorph>) -> Result<()>;
+    // With the game the plot ids belong to, the save's own table first
+    fn plot_tables(&self) -> Vec<(PlotGame, &dyn PlotAccess)>;
+    fn plot_tables_mut(&mut self) -> Vec<(PlotGame, &mut dyn PlotAccess)>;
+}
+
+// The player, the squad and the head morph are read from player.sav
+impl SaveGame for Me1SaveGame {
+    fn player_name(&self) -> Option<&str> {
+        self.player.player_name()
+    }
+
+    fn level(&self) -> Option<i32> {
+        self.player.level()
+    }
+
+    fn credits(&self) -> Option<i32> {
+        self.player.credits()
+    }
+
+    fn is_female(&self) -> Option<bool> {
+        self.player.is_female()
+    }
+
+    fn difficulty(&self) -> Option<String> {
+        self.player.difficulty()
+    }
+
+    fn squad(&self) -> Vec<&str> {
+        self.player.squad()
+    }
+
+    fn timestamp(&self) -> Option<&SaveTimeStamp> {
+        None
+    }
+
+    // Read from the package on each call
+    fn head_morph(&self) -> Option<Cow<'_, Option<HeadMorph>>> {
+        self.player.head_morph().map(Cow::Owned)
+    }
+
+    fn set_head_morph(&mut self, head_morph: Option<HeadMorph>) -> Result<()> {
+        self.player.set_head_morph(&head_morph)
+    }
+
+    fn plot_tables(&self) -> Vec<(PlotGame, &dyn PlotAccess)> {
+        vec![(PlotGame::Me1, &self.state.plot)]
+    }
+
+    fn plot_tables_mut(&mut self) -> Vec<(PlotGame, &mut dyn PlotAccess)> {
+        vec![(PlotGame::Me1, &mut self.state.plot)]
+    }
+}
+
+impl SaveGame for Me1LeSaveData {
+    fn player_name(&self) -> Option<&str> {
+        Some(&self.player.first_name)
+    }
+
+    fn level(&self) -> Option<i32> {
+        Some(self.player.level)
+    }
+
+    fn credits(&self) -> Option<i32> {
+        Some(self.player.credits)
+    }
+
+    fn is_female(&self) -> Option<bool> {
+        Some(self.player.is_female)
+    }
+
+    // The first game option
+    fn difficulty(&self) -> Option<String> {
+        self.player.game_options.first().copied().map(difficulty_name)
+    }
+
+    fn squad(&self) -> Vec<&str> {
+        self.squad.iter().map(|henchman| henchman.tag.as_str()).collect()
+    }
+
+    fn timestamp(&self) -> Option<&SaveTimeStamp> {
+        Some(&self.timestamp)
+    }
+
+    fn head_morph(&self) -> Option<Cow<'_, Option<HeadMorph>>> {
+        Some(Cow::Borrowed(&self.player.head_morph))
+    }
+
+    fn set_head_morph(&mut self, head_morph: Option<HeadMorph>) -> Result<()> {
+        self.player.head_morph = head_morph;
+        Ok(())
+    }
+
+    fn plot_tables(&self) -> Vec<(PlotGame, &dyn PlotAccess)> {
+        vec![(PlotGame::Me1, &self.plot)]
+    }
+
+    fn plot_tables_mut(&mut self) -> Vec<(PlotGame, &mut dyn PlotAccess)> {
+        vec![(PlotGame::Me1, &mut self.plot)]
+    }
+}
+
+macro_rules! delegate_save_game {
+    ($self:ident => $inner:expr, $inner_mut:expr) => {
+        fn player_name(&self) -> Option<&str> {
+            let $self = self;
+            $inner.player_name()
+        }
+
+        fn level(&self) -> Option<i32> {
+            let $self = self;
+            $inner.level()
+        }
+
+        fn credits(&self) -> Option<i32> {
+            let $self = self;
+            $inner.credits()
+        }
+
+        fn is_female(&self) -> Option<bool> {
+            let $self = self;
+            $inner.is_female()
+        }
+
+        fn difficulty(&self) -> Option<String> {
+            let $self = self;
+            $inner.difficulty()
+        }
+
+        fn squad(&self) -> Vec<&str> {
+            let $self = self;
+            $inner.squad()
+        }
+
+        fn timestamp(&self) -> Option<&SaveTimeStamp> {
+            let $self = self;
+            $inner.timestamp()
+        }
+
+        fn head_morph(&self) -> Option<Cow<'_, Option<HeadMorph>>> {
+            let $self = self;
+            $inner.head_morph()
+        }
+
+        fn set_head_morph(&mut self, head_morph: Option<HeadMorph>) -> Result<()> {
+            let $self = self;
+            $inner_mut.set_head_morph(head_morph)
+        }
+
+        fn plot_tables(&self) -> Vec<(PlotGame, &dyn PlotAccess)> {
+            let $self = self;
+            $inner.plot_tables()
+        }
+
+        fn plot_tables_mut(&mut self) -> Vec<(PlotGame, &mut dyn PlotAccess)> {
+            let $self = self;
+            $inner_mut.plot_tables_mut()
+        }
+    };
+}
+
+impl SaveGame for Me1LeSaveGame {
+    delegate_save_game!(this => this.save_data, this.save_data);
+}
+
+// ME2 and ME3 saves share the same layout for these fields
+macro_rules! impl_save_game {
+    ($save_game:ty, $plot_game:expr) => {
+        impl SaveGame for $save_game {
+            fn player_name(&self) -> Option<&str> {
+                Some(&self.player.first_name)
+            }
+
+            fn level(&self) -> Option<i32> {
+                Some(self.player.level)
+            }
+
+            fn credits(&self) -> Option<i32> {
+                Some(self.player.credits)
+            }
+
+            fn is_female(&self) -> Option<bool> {
+                Some(self.player.is_female)
+            }
+
+            fn difficulty(&self) -> Option<String> {
+                Some(format!("{:?}", self.difficulty))
+            }
+
+            fn squad(&self) -> Vec<&str> {
+                self.squad.iter().map(|henchman| henchman.tag.as_str()).collect()
+            }
+
+            fn timestamp(&self) -> Option<&SaveTimeStamp> {
+                Some(&self.timestamp)
+            }
+
+            fn head_morph(&self) -> Option<Cow<'_, Option<HeadMorph>>> {
+                Some(Cow::Borrowed(&self.player.appearance.head_morph))
+            }
+
+            fn set_head_morph(&mut self, head_morph: Option<HeadMorph>) -> Result<()> {
+                self.player.appearance.head_morph = head_morph;
+                Ok(())
+            }
+
+            fn plot_tables(&self) -> Vec<(PlotGame, &dyn PlotAccess)> {
+                vec![($plot_game, &self.plot), (PlotGame::Me1, &self.me1_plot)]
+            }
+
+            fn plot_tables_mut(&mut self) -> Vec<(PlotGame, &mut dyn PlotAccess)> {
+                vec![($plot_game, &mut self.plot), (PlotGame::Me1, &mut self.me1_plot)]
+            }
+        }
+    };
+}
+
+impl_save_game!(Me2SaveGame, PlotGame::Me2);
+impl_save_game!(Me2LeSaveGame, PlotGame::Me2);
+impl_save_game!(Me3SaveGame, PlotGame::Me3);
+
+// CRC-32/BZIP2 checksum stored in a save file
+pub struct Checksum {
+    pub stored: u32,
+    pub computed: u32,
+    offset: usize,
+    is_be: bool,
+}
+
+impl Checksum {
+    // From the first bytes of the file and its end only, the checksum of a save that does not
+    // parse can still be repaired. ME1 and ME1LE PS4 saves have no checksum
+    pub fn read(file: &[u8]) -> Option<Checksum> {
+        let (offset, is_be) = match Format::detect(file)? {
+            Format::Me1 | Format::Me1LePs4 => return None,
+            Format::Me1Le => (file.len().checked_sub(12)?, false),
+            Format::Me2Le => (file.len().checked_sub(4)?, false),
+            Format::Me2 { is_xbox360 } | Format::Me3 { is_xbox360 } => (file.len().checked_sub(4)?, is_xbox360),
+        };
+
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&file[offset..offset + 4]);
+        let stored = if is_be {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+
+        let crc = Crc::<u32>::new(&CRC_32_BZIP2);
+        let computed = crc.checksum(&file[..offset]);
+
+        Some(Checksum {
+            stored,
+            computed,
+            offset,
+            is_be,
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.stored == self.computed
+    }
+
+    // Only rewrites the 4 bytes of the checksum
+    pub fn repair(&self, file: &mut [u8]) {
+        let bytes = if self.is_be {
+            u32::to_be_bytes(self.computed)
+        } else {
+            u32::to_le_bytes(self.computed)
+        };
+        file[self.offset..self.offset + 4].copy_from_slice(&bytes);
+    }
+}
+
+// Told apart by the first bytes of the file, before the rest of the save is parsed
+#[derive(Clone, Copy)]
+enum Format {
+    Me1,
+    Me1Le,
+    Me1LePs4,
+    Me2 { is_xbox360: bool },
+    Me2Le,
+    Me3 { is_xbox360: bool },
+}
+
+impl Format {
+    fn detect(input: &[u8]) -> Option<Self> {
+        fn header<'de, T>(header: &'de [u8]) -> Result<T, unreal::Error>
+        where
+            T: Deserialize<'de>,
+        {
+            unreal::Deserializer::from_bytes::<T>(header)
+        }
+
+        let format = if header::<Me1MagicNumber>(input).is_ok() {
+            Format::Me1
+        } else if header::<Me1LeMagicNumber>(input).is_ok() {
+            Format::Me1Le
+        } else if header::<Me1LeVersion>(input).is_ok() {
+            Format::Me1LePs4
+        } else if let Ok(save) = header::<Me2Version>(input) {
+            Format::Me2 {
+                is_xbox360: save.is_xbox360,
+            }
+        } else if header::<Me2LeVersion>(input).is_ok() {
+            Format::Me2Le
+        } else if let Ok(save) = header::<Me3Version>(input) {
+            Format::Me3 {
+                is_xbox360: save.is_xbox360,
+            }
+        } else {
+            return None;
+        };
+        Some(format)
+    }
+}
+
+#[derive(From, Deserialize, Serialize)]
 pub enum SaveKind {
+    MassEffect1(Box<Me1SaveGame>),
     MassEffect1Le(Box<Me1LeSaveGame>),
     MassEffect1LePs4(Box<Me1LeSaveData>),
     MassEffect2(Box<Me2SaveGame>),
@@ -23,52 +356,120 @@ pub enum SaveKind {
     MassEffect3(Box<Me3SaveGame>),
 }
 
+impl SaveGame for SaveKind {
+    delegate_save_game!(this => this.save_game(), this.save_game_mut());
+}
+
 impl SaveKind {
-    pub fn deserialize(input: Vec<u8>) -> Result<Self> {
-        fn header<'de, T>(header: &'de [u8]) -> Result<T, unreal::Error>
-        where
-            T: Deserialize<'de>,
-        {
-            unreal::Deserializer::from_bytes::<T>(header)
+    fn save_game(&self) -> &dyn SaveGame {
+        match self {
+            SaveKind::MassEffect1(save_game) => save_game.as_ref(),
+            SaveKind::MassEffect1Le(save_game) => save_game.as_ref(),
+            SaveKind::MassEffect1LePs4(save_game) => save_game.as_ref(),
+            SaveKind::MassEffect2(save_game) => save_game.as_ref(),
+            SaveKind::MassEffect2Le(save_game) => save_game.as_ref(),
+            SaveKind::MassEffect3(save_game) => save_game.as_ref(),
         }
+    }
 
-        let this = if header::<Me1LeMagicNumber>(&input).is_ok() {
-            // ME1 Legendary
-            let me1_le: Me1LeSaveGame = unreal::Deserializer::from_bytes(&input)?;
-            Box::new(me1_le).into()
-        } else if header::<Me1LeVersion>(&input).is_ok() {
-            // ME1LE PS4
-            let me1_le_ps4: Me1LeSaveData = unreal::Deserializer::from_bytes(&input)?;
-            Box::new(me1_le_ps4).into()
-        } else if let Ok(save) = header::<Me2Version>(&input) {
-            // ME2
-            let me2: Me2SaveGame = if save.is_xbox360 {
-                unreal::Deserializer::from_be_bytes(&input)?
-            } else {
-                unreal::Deserializer::from_bytes(&input)?
-            };
-            Box::new(me2).into()
-        } else if header::<Me2LeVersion>(&input).is_ok() {
-            // ME2 Legendary
-            let me2_le: Me2LeSaveGame = unreal::Deserializer::from_bytes(&input)?;
-            Box::new(me2_le).into()
-        } else if let Ok(save) = header::<Me3Version>(&input) {
-            // ME3
-            let me3: Me3SaveGame = if save.is_xbox360 {
-                unreal::Deserializer::from_be_bytes(&input)?
-            } else {
-                unreal::Deserializer::from_bytes(&input)?
-            };
-            Box::new(me3).into()
-        } else {
-            bail!("Unsupported file");
+    fn save_game_mut(&mut self) -> &mut dyn SaveGame {
+        match self {
+            SaveKind::MassEffect1(save_game) => save_game.as_mut(),
+            SaveKind::MassEffect1Le(save_game) => save_game.as_mut(),
+            SaveKind::MassEffect1LePs4(save_game) => save_game.as_mut(),
+            SaveKind::MassEffect2(save_game) => save_game.as_mut(),
+            SaveKind::MassEffect2Le(save_game) => save_game.as_mut(),
+            SaveKind::MassEffect3(save_game) => save_game.as_mut(),
+        }
+    }
+
+    pub fn deserialize(input: Vec<u8>) -> Result<Self> {
+        Self::deserialize_with(input, &ClassSizes::default())
+    }
+
+    // `class_sizes` are the sizes of the ME1LE legacy object classes unknown to the editor
+    pub fn deserialize_with(input: Vec<u8>, class_sizes: &ClassSizes) -> Result<Self> {
+        class_sizes.scope(|| Self::deserialize_game(input))
+    }
+
+    fn deserialize_game(input: Vec<u8>) -> Result<Self> {
+        let this = match Format::detect(&input) {
+            Some(Format::Me1) => {
+                let me1: Me1SaveGame = unreal::Deserializer::from_bytes(&input)?;
+                Box::new(me1).into()
+            }
+            Some(Format::Me1Le) => {
+                let me1_le: Me1LeSaveGame = unreal::Deserializer::from_bytes(&input)?;
+                Box::new(me1_le).into()
+            }
+            Some(Format::Me1LePs4) => {
+                let me1_le_ps4: Me1LeSaveData = unreal::Deserializer::from_bytes(&input)?;
+                Box::new(me1_le_ps4).into()
+            }
+            Some(Format::Me2 { is_xbox360 }) => {
+                let me2: Me2SaveGame = if is_xbox360 {
+                    unreal::Deserializer::from_be_bytes(&input)?
+                } else {
+                    unreal::Deserializer::from_bytes(&input)?
+                };
+                Box::new(me2).into()
+            }
+            Some(Format::Me2Le) => {
+                let me2_le: Me2LeSaveGame = unreal::Deserializer::from_bytes(&input)?;
+                Box::new(me2_le).into()
+            }
+            Some(Format::Me3 { is_xbox360 }) => {
+                let me3: Me3SaveGame = if is_xbox360 {
+                    unreal::Deserializer::from_be_bytes(&input)?
+                } else {
+                    unreal::Deserializer::from_bytes(&input)?
+                };
+                Box::new(me3).into()
+            }
+            None => bail!("Unsupported file"),
         };
 
         Ok(this)
     }
 
+    // The game, whatever the edition and the platform
+    pub fn game(&self) -> PlotGame {
+        match self {
+            SaveKind::MassEffect1(_) | SaveKind::MassEffect1Le(_) | SaveKind::MassEffect1LePs4(_) => PlotGame::Me1,
+            SaveKind::MassEffect2(_) | SaveKind::MassEffect2Le(_) => PlotGame::Me2,
+            SaveKind::MassEffect3(_) => PlotGame::Me3,
+        }
+    }
+
+    pub fn is_xbox360(&self) -> bool {
+        match self {
+            SaveKind::MassEffect2(me2) => me2.is_xbox360(),
+            SaveKind::MassEffect3(me3) => me3.is_xbox360(),
+            _ => false,
+        }
+    }
+
+    pub fn set_xbox360(&mut self, is_xbox360: bool) {
+        match self {
+            SaveKind::MassEffect2(me2) => me2.set_xbox360(is_xbox360),
+            SaveKind::MassEffect3(me3) => me3.set_xbox360(is_xbox360),
+            _ => (),
+        }
+    }
+
     pub fn serialize(&self, path: &Path) -> Result<Vec<u8>> {
+        let is_xbox360 = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("xbsav"))
+            .unwrap_or_default();
+
+        self.serialize_for_platform(is_xbox360)
+    }
+
+    // Only ME2 and ME3 can be serialized for Xbox 360, `is_xbox360` is ignored for the other games
+    pub fn serialize_for_platform(&self, is_xbox360: bool) -> Result<Vec<u8>> {
         let output = match self {
+            SaveKind::MassEffect1(ref save_data) => unreal::Serializer::to_vec(save_data)?,
             SaveKind::MassEffect1Le(ref save_data) => {
                 let mut output = unreal::Serializer::to_vec(save_data)?;
 
@@ -84,11 +485,6 @@ impl SaveKind {
             }
             SaveKind::MassEffect1LePs4(ref save_data) => unreal::Serializer::to_vec(save_data)?,
             SaveKind::MassEffect2(ref save_data) => {
-                let is_xbox360 = path
-                    .extension()
-                    .map(|ext| ext.eq_ignore_ascii_case("xbsav"))
-                    .unwrap_or_default();
-
                 let mut output = if is_xbox360 {
                     unreal::Serializer::to_be_vec(save_data)?
                 } else {
@@ -115,11 +511,6 @@ impl SaveKind {
                 output
             }
             SaveKind::MassEffect3(ref save_data) => {
-                let is_xbox360 = path
-                    .extension()
-                    .map(|ext| ext.eq_ignore_ascii_case("xbsav"))
-                    .unwrap_or_default();
-
                 let mut output = if is_xbox360 {
                     unreal::Serializer::to_be_vec(save_data)?
                 } else {
@@ -142,3 +533,123 @@ impl SaveKind {
         Ok(output)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use anyhow::Result;
+
+    use super::*;
+
+    #[test]
+    fn ron_round_trip() -> Result<()> {
+        let files = [
+            "test/ME1Save.MassEffectSave",
+            "test/ME1LeSave.pcsav",
+            "test/ME1LeExport.pcsav",
+            "test/ME1LeSave.uncompressed",
+            "test/ME2Save.pcsav",
+            "test/ME2Save360.xbsav",
+            "test/ME2LeSave.pcsav",
+            "test/ME3Save.pcsav",
+            "test/ME3Save360.xbsav",
+        ];
+
+        for file in files {
+            let path = Path::new(file);
+            let input = fs::read(path)?;
+
+            // Binary => RON => binary
+            let save_game = SaveKind::deserialize(input)?;
+            let expected = save_game.serialize(path)?;
+
+            let dump = ron::ser::to_string(&save_game)?;
+            let save_game: SaveKind = ron::de::from_str(&dump)?;
+            let output = save_game.serialize(path)?;
+
+            // Check RON round trip = binary round trip
+            assert!(expected == output, "{file}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn save_game_trait() -> Result<()> {
+        let files = [
+            ("test/ME1LeSave.pcsav", 1),
+            ("test/ME1LeSave.uncompressed", 1),
+            ("test/ME2Save360.xbsav", 2),
+            ("test/ME2LeSave.pcsav", 2),
+            ("test/ME3Save.pcsav", 2),
+        ];
+
+        for (file, plot_tables) in files {
+            let save_game = SaveKind::deserialize(fs::read(file)?)?;
+            assert_eq!(save_game.player_name(), Some("Clare"), "{file}");
+            assert!(save_game.level().is_some() && save_game.timestamp().is_some(), "{file}");
+            assert!(!save_game.squad().is_empty(), "{file}");
+            assert_eq!(save_game.plot_tables().len(), plot_tables, "{file}");
+        }
+
+        // No timestamp in ME1 saves
+        let me1 = SaveKind::deserialize(fs::read("test/ME1Save.MassEffectSave")?)?;
+        assert_eq!(me1.player_name(), Some("Clare"));
+        assert!(me1.level().is_some() && me1.timestamp().is_none());
+        assert!(!me1.squad().is_empty() && matches!(me1.head_morph().as_deref(), Some(Some(_))));
+        assert_eq!(me1.plot_tables().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn checksum() -> Result<()> {
+        let files = [
+            "test/ME1LeSave.pcsav",
+            "test/ME2Save.pcsav",
+            "test/ME2Save360.xbsav",
+            "test/ME2LeSave.pcsav",
+            "test/ME3Save.pcsav",
+            "test/ME3Save360.xbsav",
+        ];
+
+        for file in files {
+            let mut input = fs::read(file)?;
+            assert!(Checksum::read(&input).unwrap().is_valid(), "{file}");
+
+            // Hand patched
+            let expected = input.clone();
+            input[100] ^= 0xff;
+            let checksum = Checksum::read(&input).unwrap();
+            assert!(!checksum.is_valid(), "{file}");
+
+            checksum.repair(&mut input);
+            assert!(Checksum::read(&input).unwrap().is_valid(), "{file}");
+            input[100] ^= 0xff;
+            assert!(input != expected && input[..100] == expected[..100], "{file}");
+        }
+
+        let input = fs::read("test/ME1Save.MassEffectSave")?;
+        assert!(Checksum::read(&input).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn platform_conversion() -> Result<()> {
+        let files = [
+            ("test/ME2Save.pcsav", "test/ME2Save360.xbsav"),
+            ("test/ME3Save.pcsav", "test/ME3Save360.xbsav"),
+        ];
+
+        for (pc, xbox360) in files {
+            let input_pc = fs::read(pc)?;
+            let input_xb360 = fs::read(xbox360)?;
+
+            let output_pc_to_xb360 = SaveKind::deserialize(input_pc.clone())?.serialize_for_platform(true)?;
+            let output_xb360_to_pc = SaveKind::deserialize(input_xb360.clone())?.serialize_for_platform(false)?;
+
+            assert!(input_xb360 == output_pc_to_xb360);
+            assert!(input_pc == output_xb360_to_pc);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use std::{collections::BTreeSet, fmt, mem, path::Path, str::FromStr};
+
+use anyhow::{bail, Error, Result};
+
+use crate::{
+    plot_db::{PlotDb, PlotGame, PlotKind},
+    plot_patch::PlotValue,
+    reflect::{self, FieldPath},
+    save_game::{SaveGame, SaveKind},
+};
+
+// The sections merged in addition to the plot tables
+const SECTIONS: [&str; 2] = ["journal", "codex"];
+
+// Which save wins a conflict
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    #[default]
+    Ours,
+    Theirs,
+}
+
+impl FromStr for Side {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let side = match s.to_ascii_lowercase().as_str() {
+            "ours" => Side::Ours,
+            "theirs" => Side::Theirs,
+            _ => bail!("Unknown side `{s}`, expected ours or theirs"),
+        };
+        Ok(side)
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Ours => f.write_str("ours"),
+            Side::Theirs => f.write_str("theirs"),
+        }
+    }
+}
+
+pub struct PlotConflict {
+    pub table: PlotGame,
+    pub kind: PlotKind,
+    pub id: usize,
+    // Resolved with the plot database
+    pub name: Option<String>,
+    pub base: PlotValue,
+    pub ours: PlotValue,
+    pub theirs: PlotValue,
+}
+
+impl fmt::Display for PlotConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let PlotConflict {
+            table,
+            kind,
+            id,
+            ref name,
+            base,
+            ours,
+            theirs,
+        } = *self;
+
+        write!(f, "{table} {kind} {id}")?;
+        if let Some(name) = name {
+            write!(f, " ({name})")?;
+        }
+        write!(f, ": base {base}, ours {ours}, theirs {theirs}")
+    }
+}
+
+#[derive(Default)]
+pub struct Conflicts {
+    pub plot: Vec<PlotConflict>,
+    pub fields: Vec<reflect::Conflict>,
+}
+
+impl Conflicts {
+    pub fn len(&self) -> usize {
+        self.plot.len() + self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn merge_plot(
+    base: &SaveKind,
+    ours: &mut SaveKind,
+    theirs: &SaveKind,
+    prefer: Side,
+    database_dir: Option<&Path>,
+) -> Result<Vec<PlotConflict>> {
+    let mut conflicts = Vec::new();
+
+    let tables = base.plot_tables().into_iter().zip(theirs.plot_tables());
+    for ((table, base_plot), (_, theirs_plot)) in tables {
+        let mut ours_tables = ours.plot_tables_mut();
+        let Some((_, ours_plot)) = ours_tables.iter_mut().find(|(game, _)| *game == table) else {
+            continue;
+        };
+        let plot_db = PlotDb::load(table, database_dir)?;
+
+        for kind in [PlotKind::Boolean, PlotKind::Integer, PlotKind::Float] {
+            let ids: BTreeSet<_> = (base_plot.ids(kind).into_iter())
+                .chain(ours_plot.ids(kind))
+                .chain(theirs_plot.ids(kind))
+                .collect();
+
+            for id in ids {
+                let base = PlotValue::get(base_plot, kind, id);
+                let ours = PlotValue::get(&**ours_plot, kind, id);
+                let theirs = PlotValue::get(theirs_plot, kind, id);
+
+                if ours == theirs || theirs == base {
+                    continue;
+                }
+                if ours != base {
+                    conflicts.push(PlotConflict {
+                        table,
+                        kind,
+                        id,
+                        name: plot_db.find(kind, id).map(|variable| variable.name.to_owned()),
+                        base,
+                        ours,
+                        theirs,
+                    });
+                    if prefer == Side::Ours {
+                        continue;
+                    }
+                }
+                theirs.set(&mut **ours_plot, id)?;
+            }
+        }
+    }
+
+    Ok(conflicts)
+}
+
+// Three-way merge of the plot tables, the journal and the codex of `theirs` into `ours`,
+// both derived from `base`. The other fields of `ours` are left untouched.
+pub fn merge(
+    base: &SaveKind,
+    ours: &mut SaveKind,
+    theirs: &SaveKind,
+    prefer: Side,
+    database_dir: Option<&Path>,
+) -> Result<Conflicts> {
+    if mem::discriminant(base) != mem::discriminant(ours) || mem::discriminant(base) != mem::discriminant(theirs) {
+        bail!("The saves are not from the same game");
+    }
+
+    let mut conflicts = Conflicts::default();
+
+    let base_tree = reflect::to_value(base)?;
+    let ours_tree = reflect::to_value(ours)?;
+    let theirs_tree = reflect::to_value(theirs)?;
+    for section in SECTIONS {
+        // Not mapped in ME1 saves
+        let Some(path) = reflect::find_field(&base_tree, section) else {
+            continue;
+        };
+
+        let (merged, section_conflicts) = reflect::merge(
+            reflect::get(&base_tree, &path)?,
+            reflect::get(&ours_tree, &path)?,
+            reflect::get(&theirs_tree, &path)?,
+            prefer == Side::Theirs,
+        );
+        reflect::edit(ours, |root| reflect::replace(root, &path, merged))?;
+
+        conflicts
+            .fields
+            .extend(section_conflicts.into_iter().map(|conflict| reflect::Conflict {
+                path: FieldPath(path.0.iter().chain(&conflict.path.0).cloned().collect()),
+                ..conflict
+            }));
+    }
+
+    conflicts.plot = merge_plot(base, ours, theirs, prefer, database_dir)?;
+
+    Ok(conflicts)
+}
+
+// Replace a field of `save_game` with the one of `from`, e.g. `player` or `plot`
+pub fn take(save_game: &mut SaveKind, from: &SaveKind, path: &FieldPath) -> Result<()> {
+    if mem::discriminant(save_game) != mem::discriminant(from) {
+        bail!("The saves are not from the same game");
+    }
+
+    let value = reflect::get(&reflect::to_value(from)?, path)?.clone();
+    reflect::edit(save_game, |root| reflect::replace(root, path, value))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+
+    fn load(path: &str) -> Result<SaveKind> {
+        SaveKind::deserialize(fs::read(path)?)
+    }
+
+    #[test]
+    fn three_way() -> Result<()> {
+        let base = load("test/ME2Save.pcsav")?;
+        let mut ours = load("test/ME2Save.pcsav")?;
+        let mut theirs = load("test/ME2Save.pcsav")?;
+
+        {
+            let (_, plot) = &mut ours.plot_tables_mut()[0];
+            plot.set_integer(10, 1)?;
+            plot.set_integer(11, 1)?;
+        }
+        {
+            let (_, plot) = &mut theirs.plot_tables_mut()[0];
+            plot.set_integer(11, 2)?;
+            plot.set_integer(12, 2)?;
+        }
+        reflect::edit(&mut theirs, |root| reflect::set(root, &"journal.quest_progress_counter".parse()?, "99"))?;
+        reflect::edit(&mut theirs, |root| reflect::set(root, &"player.credits".parse()?, "1"))?;
+
+        let conflicts = merge(&base, &mut ours, &theirs, Side::Ours, None)?;
+        assert_eq!(conflicts.len(), 1);
+        assert!(matches!(conflicts.plot[0], PlotConflict { id: 11, .. }));
+
+        let (_, plot) = ours.plot_tables()[0];
+        assert_eq!((plot.integer(10), plot.integer(11), plot.integer(12)), (1, 1, 2));
+        let counter = reflect::get_field(&ours, &"journal.quest_progress_counter".parse()?)?;
+        assert_eq!(counter.as_i64(), Some(99));
+        // Only the plot, the journal and the codex are merged
+        assert_ne!(ours.credits(), Some(1));
+
+        // Still a valid save
+        SaveKind::deserialize(ours.serialize_for_platform(false)?)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn take_field() -> Result<()> {
+        let mut save_game = load("test/ME2Save.pcsav")?;
+        let mut from = load("test/ME2Save.pcsav")?;
+        reflect::edit(&mut from, |root| reflect::set(root, &"player.credits".parse()?, "1"))?;
+
+        take(&mut save_game, &from, &"player".parse()?)?;
+        assert_eq!(save_game.credits(), Some(1));
+
+        assert!(take(&mut save_game, &load("test/ME3Save.pcsav")?, &"player".parse()?).is_err());
+
+        Ok(())
+    }
+}
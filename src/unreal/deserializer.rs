@@ -6,31 +6,48 @@ use serde::de::{
 };
 use serde::Deserialize;
 
-use super::Result;
+use super::{ErrorKind, PathSegment, Result};
 
 pub struct Deserializer<'de> {
     input: &'de [u8],
     is_le: bool,
+    offset: usize,
 }
 
 impl<'de> Deserializer<'de> {
     pub fn from_bytes<T: Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
-        let mut deserializer = Deserializer { input, is_le: true };
-        T::deserialize(&mut deserializer)
+        Self::new(input, true).deserialize()
     }
 
     pub fn from_be_bytes<T: Deserialize<'de>>(input: &'de [u8]) -> Result<T> {
-        let mut deserializer = Deserializer { input, is_le: false };
-        T::deserialize(&mut deserializer)
+        Self::new(input, false).deserialize()
+    }
+
+    fn new(input: &'de [u8], is_le: bool) -> Self {
+        Deserializer {
+            input,
+            is_le,
+            offset: 0,
+        }
+    }
+
+    fn deserialize<T: Deserialize<'de>>(mut self) -> Result<T> {
+        T::deserialize(&mut self).map_err(|err| err.locate(self.offset))
+    }
+
+    // Errors happening in `f` are located at the current offset, under `segment`
+    fn located<T>(&mut self, segment: PathSegment, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        f(self).map_err(|err| err.locate(self.offset).under(segment))
     }
 
     fn read(&mut self, len: usize) -> Result<&[u8]> {
         if len > self.input.len() {
-            return Err(super::Error::Eof);
+            return Err(ErrorKind::Eof.into());
         }
 
         let (slice, remaining) = self.input.split_at(len);
         self.input = remaining;
+        self.offset += len;
 
         Ok(slice)
     }
@@ -41,12 +58,12 @@ impl<'de> Deserializer<'de> {
 }
 
 macro_rules! unimpl_deserialize {
-    ($de_method:ident()) => {
+    ($de_method:ident($type:ident)) => {
         fn $de_method<V>(self, _: V) -> Result<V::Value>
         where
             V: Visitor<'de>,
         {
-            unimplemented!()
+            Err(ErrorKind::UnsupportedType(stringify!($type)).into())
         }
     };
 }
@@ -73,7 +90,7 @@ macro_rules! impl_deserialize {
     };
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = super::Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
@@ -99,12 +116,12 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     // Signed ints
-    unimpl_deserialize!(deserialize_i8());
-    unimpl_deserialize!(deserialize_i16());
+    unimpl_deserialize!(deserialize_i8(i8));
+    unimpl_deserialize!(deserialize_i16(i16));
 
     impl_deserialize!(deserialize_i32(i32) = visit_i32()); // Impl
 
-    unimpl_deserialize!(deserialize_i64());
+    unimpl_deserialize!(deserialize_i64(i64));
 
     // Unsigned ints
     impl_deserialize!(deserialize_u8(u8) = visit_u8()); // Impl
@@ -115,10 +132,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     // Floats
     impl_deserialize!(deserialize_f32(f32) = visit_f32()); // Impl
 
-    unimpl_deserialize!(deserialize_f64());
+    unimpl_deserialize!(deserialize_f64(f64));
 
     // Char
-    unimpl_deserialize!(deserialize_char());
+    unimpl_deserialize!(deserialize_char(char));
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
@@ -138,7 +155,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
         let mut string = if len < 0 {
             // Unicode
-            let string_len = (len.abs() * 2) as usize;
+            let string_len = len.unsigned_abs() as usize * 2;
             let bytes = self.read(string_len)?.to_owned();
 
             let (decoded, _, had_errors) = if self.is_le {
@@ -199,14 +216,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        Err(super::Error::Eof)
+        Err(ErrorKind::Eof.into())
     }
 
     fn deserialize_unit_struct<V>(self, _: &'static str, _: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("unit_struct").into())
     }
 
     fn deserialize_newtype_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
@@ -246,36 +263,22 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_tuple(len, visitor)
     }
 
-    fn deserialize_struct<V>(self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    fn deserialize_struct<V>(self, _: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_tuple_struct(name, fields.len(), visitor)
+        visitor.visit_seq(SizedSeqMap::with_fields(self, fields))
     }
 
     fn deserialize_enum<V>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        impl<'de, 'a> EnumAccess<'de> for &'a mut Deserializer<'de> {
-            type Error = super::Error;
-            type Variant = Self;
-
-            fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
-            where
-                V: DeserializeSeed<'de>,
-            {
-                let idx: u8 = de::Deserialize::deserialize(&mut *self)?;
-                let val = seed.deserialize(idx.into_deserializer())?;
-                Ok((val, self))
-            }
-        }
-
         visitor.visit_enum(self)
     }
 
-    unimpl_deserialize!(deserialize_identifier());
-    unimpl_deserialize!(deserialize_ignored_any());
+    unimpl_deserialize!(deserialize_identifier(identifier));
+    unimpl_deserialize!(deserialize_ignored_any(ignored_any));
 
     fn is_human_readable(&self) -> bool {
         false
@@ -285,11 +288,34 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 struct SizedSeqMap<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
     len: usize,
+    idx: usize,
+    fields: Option<&'static [&'static str]>,
 }
 
 impl<'a, 'de> SizedSeqMap<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>, len: usize) -> Self {
-        SizedSeqMap { de, len }
+        SizedSeqMap {
+            de,
+            len,
+            idx: 0,
+            fields: None,
+        }
+    }
+
+    fn with_fields(de: &'a mut Deserializer<'de>, fields: &'static [&'static str]) -> Self {
+        SizedSeqMap {
+            de,
+            len: fields.len(),
+            idx: 0,
+            fields: Some(fields),
+        }
+    }
+
+    fn segment(&self) -> PathSegment {
+        match self.fields {
+            Some(fields) => PathSegment::Field(fields[self.idx]),
+            None => PathSegment::Index(self.idx),
+        }
     }
 }
 
@@ -309,7 +335,9 @@ impl<'de, 'a> SeqAccess<'de> for SizedSeqMap<'a, 'de> {
         }
         self.len -= 1;
 
-        seed.deserialize(&mut *self.de).map(Some)
+        let segment = self.segment();
+        self.idx += 1;
+        self.de.located(segment, |de| seed.deserialize(de)).map(Some)
     }
 }
 
@@ -329,18 +357,35 @@ impl<'de, 'a> MapAccess<'de> for SizedSeqMap<'a, 'de> {
         }
         self.len -= 1;
 
-        seed.deserialize(&mut *self.de).map(Some)
+        let segment = self.segment();
+        self.de.located(segment, |de| seed.deserialize(de)).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
     where
         V: DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.de)
+        let segment = self.segment();
+        self.idx += 1;
+        self.de.located(segment, |de| seed.deserialize(de))
+    }
+}
+
+impl<'de> EnumAccess<'de> for &mut Deserializer<'de> {
+    type Error = super::Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let idx: u8 = de::Deserialize::deserialize(&mut *self)?;
+        let val = seed.deserialize(IntoDeserializer::<super::Error>::into_deserializer(idx))?;
+        Ok((val, self))
     }
 }
 
-impl<'de, 'a> VariantAccess<'de> for &'a mut Deserializer<'de> {
+impl<'de> VariantAccess<'de> for &mut Deserializer<'de> {
     type Error = super::Error;
 
     fn unit_variant(self) -> Result<()> {
@@ -358,14 +403,13 @@ impl<'de, 'a> VariantAccess<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("tuple_variant").into())
     }
 
     fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        use serde::de::Deserializer;
-        self.deserialize_tuple(fields.len(), visitor)
+        visitor.visit_seq(SizedSeqMap::with_fields(self, fields))
     }
 }
@@ -3,6 +3,7 @@ mod serializer;
 
 pub use self::{deserializer::*, serializer::*};
 
+use std::cell::RefCell;
 use std::fmt::{self, Display};
 
 use serde::{de, ser};
@@ -10,33 +11,236 @@ use serde::{de, ser};
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Clone, Debug)]
-pub enum Error {
+pub struct Error {
+    pub kind: ErrorKind,
+    // Byte offset in the input, only known when deserializing
+    pub offset: Option<usize>,
+    // What the offset is relative to when it is not the input, e.g. `the decompressed save data`
+    pub within: Option<&'static str>,
+    // Serde field path, e.g. `player.appearance.head_morph.lod0_vertices[312]`
+    pub path: String,
+    // Length of the nested input the offset is relative to, it ends at the current offset of the outer input
+    nested_len: Option<usize>,
+}
+
+// How the input of a nested deserializer relates to the outer input
+pub enum Nested {
+    // Bytes just read from the outer input, the offset is rebased on the outer input
+    Read { len: usize },
+    // Bytes decompressed or unzipped from a field of the outer input, the offset stays relative to them
+    Extracted { field: &'static str, within: &'static str },
+}
+
+thread_local! {
+    // Error of a nested deserializer on its way through the error type of the outer one
+    static NESTED: RefCell<Option<Error>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug)]
+pub enum ErrorKind {
     Message(String),
     Eof,
+    // A legacy object whose class is not known
+    UnknownClass(String),
+    // A sequence or a compressed chunk shorter than announced
+    TruncatedChunk { len: usize, expected: String },
+    // A serde data type the format does not support
+    UnsupportedType(&'static str),
+}
+
+impl Error {
+    fn new(kind: ErrorKind) -> Self {
+        Error {
+            kind,
+            offset: None,
+            within: None,
+            path: String::new(),
+            nested_len: None,
+        }
+    }
+
+    // Only the innermost offset is kept, it is the most precise one
+    fn locate(mut self, offset: usize) -> Self {
+        match (self.offset, self.nested_len.take()) {
+            (None, _) => self.offset = Some(offset),
+            (Some(nested_offset), Some(len)) => self.offset = Some(offset - len + nested_offset),
+            (Some(_), None) => (),
+        }
+        self
+    }
+
+    // The path is built on the way out, from the innermost segment outwards
+    fn under(mut self, segment: PathSegment) -> Self {
+        let mut path = segment.to_string();
+        if !self.path.is_empty() && !self.path.starts_with('[') {
+            path.push('.');
+        }
+        path.push_str(&self.path);
+        self.path = path;
+        self
+    }
+
+    // Turns the error of a nested `Deserializer` into the error of the outer deserializer, an
+    // `unreal::Error` gets back the kind, the offset and the path instead of a message
+    pub fn nested<E: de::Error>(mut self, nested: Nested) -> E {
+        match nested {
+            Nested::Read { len } => self.nested_len = Some(len),
+            Nested::Extracted { field, within } => {
+                self.within = Some(within);
+                self = self.under(PathSegment::Field(field));
+            }
+        }
+
+        NESTED.with(|cell| *cell.borrow_mut() = Some(self.clone()));
+        let err = E::custom(&self);
+        NESTED.with(|cell| cell.borrow_mut().take());
+        err
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind)
+    }
 }
 
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Error {
-        Error::Message(msg.to_string())
+        Error::new(ErrorKind::Message(msg.to_string()))
     }
 }
 
 impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Error {
-        Error::Message(msg.to_string())
+        let msg = msg.to_string();
+        match NESTED.with(|cell| cell.borrow_mut().take()) {
+            Some(nested) if nested.to_string() == msg => nested,
+            _ => Error::new(ErrorKind::Message(msg)),
+        }
+    }
+
+    fn invalid_length(len: usize, expected: &dyn de::Expected) -> Self {
+        Error::new(ErrorKind::TruncatedChunk {
+            len,
+            expected: expected.to_string(),
+        })
+    }
+
+    // The binary format dispatches on class names, not on variant names
+    fn unknown_variant(variant: &str, _: &'static [&'static str]) -> Self {
+        Error::new(ErrorKind::UnknownClass(variant.to_owned()))
     }
 }
 
-impl Display for Error {
+impl Display for ErrorKind {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::Message(msg) => formatter.write_str(msg),
-            Error::Eof => formatter.write_str(
+            ErrorKind::Message(msg) => formatter.write_str(msg),
+            ErrorKind::Eof => formatter.write_str(
                 "Unexpected end of file, some data in your save are unexpected or your save is corrupted ?\n\
                 Save again and retry. If this error persists, please report a bug with your save attached",
             ),
+            ErrorKind::UnknownClass(class_name) => write!(formatter, "Unknown class `{class_name}`"),
+            ErrorKind::TruncatedChunk { len, expected } => {
+                write!(formatter, "Truncated data, found {len} element(s), expected {expected}")
+            }
+            ErrorKind::UnsupportedType(ty) => write!(formatter, "Unsupported data type `{ty}`"),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(formatter)?;
+        if let Some(offset) = self.offset {
+            write!(formatter, "\nAt byte offset {offset} ({offset:#x})")?;
+            if let Some(within) = self.within {
+                write!(formatter, " of {within}")?;
+            }
+            if !self.path.is_empty() {
+                write!(formatter, ", in `{}`", self.path)?;
+            }
         }
+        Ok(())
     }
 }
 
 impl std::error::Error for Error {}
+
+#[derive(Clone, Copy)]
+enum PathSegment {
+    Field(&'static str),
+    Index(usize),
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathSegment::Field(field) => formatter.write_str(field),
+            PathSegment::Index(idx) => write!(formatter, "[{idx}]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::save_data::{
+        mass_effect_1_le::{legacy::BaseObject, Me1LeSaveData},
+        mass_effect_2::Me2SaveGame,
+    };
+
+    #[test]
+    fn error_location() -> Result<()> {
+        let input = fs::read("test/ME2Save.pcsav")?;
+
+        let err = Deserializer::from_bytes::<Me2SaveGame>(&input[..20000]).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::Eof));
+        assert_eq!(err.offset, Some(19999));
+        assert_eq!(err.path, "player.appearance.head_morph.lod0_vertices[1189].x");
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_error_location() -> Result<()> {
+        // The legacy objects at the end are parsed by a nested deserializer
+        let input = fs::read("test/ME1LeSave.uncompressed")?;
+        let input = &input[..input.len() - 10];
+
+        let err = Deserializer::from_bytes::<Me1LeSaveData>(input).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::Eof));
+        // An `i32` cut in half, located in the whole input and not in the legacy section
+        assert_eq!(err.offset, Some(input.len() - 2));
+        assert!(err.within.is_none());
+        assert_eq!(err.path, "no_export.mako.localized_last_name");
+
+        Ok(())
+    }
+
+    #[test]
+    fn no_panic() -> Result<()> {
+        // Class name, owner name, no owner class
+        let mut input = Vec::new();
+        input.extend(8_i32.to_le_bytes());
+        input.extend(b"Unknown\0");
+        input.extend(1_i32.to_le_bytes());
+        input.push(0);
+        input.extend(0_u32.to_le_bytes());
+
+        let err = Deserializer::from_bytes::<BaseObject>(&input).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::UnknownClass(ref class_name) if class_name == "Unknown"));
+
+        let err = Deserializer::from_bytes::<i64>(&[0; 8]).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::UnsupportedType("i64")));
+
+        let err = Serializer::to_vec(&'c').err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::UnsupportedType("char")));
+
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use encoding_rs::WINDOWS_1252;
 use serde::ser::{self, Error};
 use serde::Serialize;
 
-use super::Result;
+use super::{ErrorKind, Result};
 
 pub struct Serializer {
     output: Vec<u8>,
@@ -42,7 +42,7 @@ impl Serializer {
 macro_rules! unimpl_serialize {
     ($ser_method:ident($type:ty)) => {
         fn $ser_method(self, _: $type) -> Result<()> {
-            unimplemented!()
+            Err(ErrorKind::UnsupportedType(stringify!($type)).into())
         }
     };
 }
@@ -61,7 +61,7 @@ macro_rules! impl_serialize {
     };
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl ser::Serializer for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
@@ -148,9 +148,9 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_bool(false)
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<()>
+    fn serialize_some<T>(self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         self.serialize_bool(true)?;
         value.serialize(self)
@@ -161,23 +161,23 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_unit_struct(self, _: &'static str) -> Result<()> {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("unit_struct").into())
     }
 
     fn serialize_unit_variant(self, _: &'static str, variant_index: u32, _: &'static str) -> Result<()> {
         self.serialize_u8(variant_index as u8)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(self, _: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(self, _: &'static str, _: u32, _: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_variant<T>(self, _: &'static str, _: u32, _: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(self)
     }
@@ -204,7 +204,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _: &'static str,
         _: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("tuple_variant").into())
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
@@ -233,13 +233,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl ser::SerializeStruct for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -249,13 +249,13 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -265,20 +265,20 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl ser::SerializeMap for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         key.serialize(&mut **self)
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -288,13 +288,13 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl ser::SerializeTupleStruct for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -304,13 +304,13 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl ser::SerializeStructVariant for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _: &'static str, value: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _: &'static str, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -320,13 +320,13 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<()>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
         value.serialize(&mut **self)
     }
@@ -336,18 +336,18 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = super::Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _: &T) -> Result<()>
+    fn serialize_field<T>(&mut self, _: &T) -> Result<()>
     where
-        T: Serialize,
+        T: ?Sized + Serialize,
     {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("tuple_variant").into())
     }
 
     fn end(self) -> Result<()> {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("tuple_variant").into())
     }
 }